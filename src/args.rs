@@ -0,0 +1,116 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct ParseArgsError(pub String);
+
+impl Display for ParseArgsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// `--name value` options and `--name` flags, read one at a time. errors name
+// the option being read
+pub struct Options<I> {
+    args: I,
+    name: String,
+}
+
+impl<I: Iterator<Item = String>> Options<I> {
+    pub fn new(args: I) -> Self {
+        Options {
+            args,
+            name: String::new(),
+        }
+    }
+
+    pub fn next_option(&mut self) -> Option<String> {
+        self.name = self.args.next()?;
+        Some(self.name.clone())
+    }
+
+    pub fn value(&mut self) -> Result<String, ParseArgsError> {
+        self.args
+            .next()
+            .ok_or_else(|| ParseArgsError(format!("missing value for {}", self.name)))
+    }
+
+    pub fn parse<T: FromStr>(&mut self) -> Result<T, ParseArgsError> {
+        let value = self.value()?;
+        value.parse().map_err(|_e| self.invalid(&value))
+    }
+
+    // a value made of two parts, like a range or a fraction
+    pub fn pair<T: FromStr>(&mut self, separator: char) -> Result<(T, T), ParseArgsError> {
+        let value = self.value()?;
+        let (a, b) = value
+            .split_once(separator)
+            .ok_or_else(|| self.invalid(&value))?;
+
+        match (a.parse(), b.parse()) {
+            (Ok(a), Ok(b)) => Ok((a, b)),
+            _ => Err(self.invalid(&value)),
+        }
+    }
+
+    pub fn invalid(&self, value: &str) -> ParseArgsError {
+        ParseArgsError(format!("invalid value for {}: {}", self.name, value))
+    }
+
+    pub fn unknown(&self) -> ParseArgsError {
+        ParseArgsError(format!("unknown argument: {}", self.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &'static str) -> Options<impl Iterator<Item = String>> {
+        Options::new(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn values() {
+        let mut options = options("--n 3 --range 1-5 --flag --name x");
+
+        assert_eq!(options.next_option().as_deref(), Some("--n"));
+        assert_eq!(options.parse::<u32>().unwrap(), 3);
+        assert_eq!(options.next_option().as_deref(), Some("--range"));
+        assert_eq!(options.pair::<u32>('-').unwrap(), (1, 5));
+        assert_eq!(options.next_option().as_deref(), Some("--flag"));
+        assert_eq!(options.next_option().as_deref(), Some("--name"));
+        assert_eq!(options.value().unwrap(), "x");
+        assert_eq!(options.next_option(), None);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |args: &'static str| {
+            let mut options = options(args);
+            options.next_option();
+            options
+        };
+
+        assert_eq!(
+            error("--n").parse::<u32>().unwrap_err().to_string(),
+            "missing value for --n"
+        );
+        assert_eq!(
+            error("--n x").parse::<u32>().unwrap_err().to_string(),
+            "invalid value for --n: x"
+        );
+        assert_eq!(
+            error("--range 1-x")
+                .pair::<u32>('-')
+                .unwrap_err()
+                .to_string(),
+            "invalid value for --range: 1-x"
+        );
+        assert_eq!(
+            error("--what").unknown().to_string(),
+            "unknown argument: --what"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 pub enum Command {
@@ -63,9 +64,9 @@ impl FromStr for Command {
         };
 
         match split.as_slice() {
-            ["forward", units] => parse_units(units).map(|u| Command::Forward(u)),
-            ["down", units] => parse_units(units).map(|u| Command::Down(u)),
-            ["up", units] => parse_units(units).map(|u| Command::Up(u)),
+            ["forward", units] => parse_units(units).map(Command::Forward),
+            ["down", units] => parse_units(units).map(Command::Down),
+            ["up", units] => parse_units(units).map(Command::Up),
             _ => Err(ParseCommandError(String::from(s))),
         }
     }
@@ -73,3 +74,9 @@ impl FromStr for Command {
 
 #[derive(Debug)]
 pub struct ParseCommandError(String);
+
+impl Display for ParseCommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid command: {}", self.0)
+    }
+}
@@ -1,11 +1,14 @@
+use std::thread;
+
 pub fn part1(report: &[String]) -> u32 {
-    let counts = count_bits(report);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let counts = count_bits_parallel(report, threads);
 
     let mut gamma_bits = Vec::new();
     let mut epsilon_bits = Vec::new();
 
-    for i in 0..counts.len() {
-        let common = counts[i] > report.len() as u32 / 2;
+    for count in counts {
+        let common = count > report.len() as u32 / 2;
 
         gamma_bits.push(u8::from(common));
         epsilon_bits.push(u8::from(!common));
@@ -56,12 +59,50 @@ pub fn part2(report: &[String]) -> u32 {
 
 fn count_bits(report: &[String]) -> Vec<u32> {
     report
-        .into_iter()
+        .iter()
         .map(|s| binary_string_to_bits(s).collect::<Vec<u32>>())
         .reduce(|counts, reading| counts.iter().zip(reading).map(|(a, b)| a + b).collect())
         .unwrap()
 }
 
+// splits the report into one chunk per thread, counts each chunk's columns
+// into a single accumulator and merges the per-chunk totals
+fn count_bits_parallel(report: &[String], threads: usize) -> Vec<u32> {
+    let chunk_size = report.len().div_ceil(threads.max(1)).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = report
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| count_bits_chunk(chunk)))
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .reduce(|mut counts, chunk_counts| {
+                counts
+                    .iter_mut()
+                    .zip(chunk_counts)
+                    .for_each(|(a, b)| *a += b);
+                counts
+            })
+            .unwrap()
+    })
+}
+
+fn count_bits_chunk(chunk: &[String]) -> Vec<u32> {
+    let mut counts = vec![0; chunk[0].len()];
+
+    for reading in chunk {
+        counts
+            .iter_mut()
+            .zip(binary_string_to_bits(reading))
+            .for_each(|(count, bit)| *count += bit);
+    }
+
+    counts
+}
+
 fn binary_string_to_bits(s: &str) -> impl Iterator<Item = u32> + '_ {
     s.chars().map(|c| if c == '1' { 1 } else { 0 })
 }
@@ -77,6 +118,7 @@ fn bits_to_binary_string(bits: Vec<u8>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rng::Rng;
 
     #[test]
     fn test_string_to_bits() {
@@ -105,9 +147,60 @@ mod tests {
         )
     }
 
+    #[test]
+    fn count_bits_parallel_matches_serial() {
+        let report: Vec<String> = synthetic_report(1000, 12);
+        let serial = count_bits(&report);
+
+        for threads in [0, 1, 2, 3, 8, 2000] {
+            assert_eq!(count_bits_parallel(&report, threads), serial);
+        }
+    }
+
+    // run with `cargo test --release -- --ignored bench_count_bits --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_count_bits() {
+        use std::time::Instant;
+
+        let report = synthetic_report(10_000_000, 12);
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+        let start = Instant::now();
+        let serial = count_bits(&report);
+        let serial_time = start.elapsed();
+
+        let start = Instant::now();
+        let parallel = count_bits_parallel(&report, threads);
+        let parallel_time = start.elapsed();
+
+        assert_eq!(parallel, serial);
+        println!(
+            "serial: {:?}, parallel ({} threads): {:?}, speedup: {:.2}x",
+            serial_time,
+            threads,
+            parallel_time,
+            serial_time.as_secs_f64() / parallel_time.as_secs_f64()
+        );
+    }
+
+    // deterministic pseudo-random report
+    fn synthetic_report(lines: usize, width: usize) -> Vec<String> {
+        let mut rng = Rng(1);
+
+        (0..lines)
+            .map(|_| {
+                let state = rng.next();
+                (0..width)
+                    .map(|i| if state >> i & 1 == 1 { '1' } else { '0' })
+                    .collect()
+            })
+            .collect()
+    }
+
     #[test]
     fn example_part1() {
-        let data: Vec<String> = vec![
+        let data: Vec<String> = [
             "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
             "11001", "00010", "01010",
         ]
@@ -120,7 +213,7 @@ mod tests {
 
     #[test]
     fn example_part2() {
-        let data: Vec<String> = vec![
+        let data: Vec<String> = [
             "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
             "11001", "00010", "01010",
         ]
@@ -1,161 +1,1076 @@
-use indexmap::IndexMap;
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, IsTerminal};
 use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
+use crate::args::{Options, ParseArgsError};
+use crate::rng::Rng;
+
+// a free space has no number and starts out marked
+#[derive(PartialEq, Debug, Clone, Copy)]
+struct Cell {
+  number: Option<u32>,
+  marked: bool,
+}
+
+impl Cell {
+  const FREE: Cell = Cell {
+    number: None,
+    marked: true,
+  };
+
+  fn new(number: u32) -> Cell {
+    Cell {
+      number: Some(number),
+      marked: false,
+    }
+  }
+}
+
+// cells are stored in row-major order, so duplicate numbers keep their
+// own positions. the number index and hit counters let `mark` detect a win
+// by only looking at the lines through the cells it just marked
 #[derive(PartialEq, Debug)]
 struct Card {
-  width: u8,
-  numbers: IndexMap<u8, bool>,
+  width: usize,
+  height: usize,
+  cells: Vec<Cell>,
+  // cell indexes holding each number that has not been marked yet
+  positions: HashMap<u32, Vec<usize>>,
+  row_hits: Vec<usize>,
+  column_hits: Vec<usize>,
+  diagonal_hits: usize,
+  anti_diagonal_hits: usize,
+  marked: usize,
+  // cells of the first winning line, empty until the card wins
+  winning_cells: Vec<usize>,
 }
 
 impl FromStr for Card {
-  type Err = ();
+  type Err = ParseBingoError;
 
   fn from_str(grid: &str) -> Result<Self, Self::Err> {
-    let lines = grid.trim().lines();
-    let width = grid
-      .trim()
-      .lines()
-      .collect::<Vec<&str>>()
-      .first()
-      .map_or(0, |x| {
-        x.split_whitespace().collect::<Vec<&str>>().len() as u8
-      });
+    Card::parse(0, &grid.trim().lines().collect::<Vec<&str>>())
+  }
+}
+
+impl Card {
+  // `index` is the card's position in the input, used in error messages
+  fn parse(index: usize, lines: &[&str]) -> Result<Card, ParseBingoError> {
+    let width = lines.first().map_or(0, |x| x.split_whitespace().count());
+    let mut cells = Vec::new();
+
+    for (row, line) in lines.iter().enumerate() {
+      let numbers = line.split_whitespace().collect::<Vec<&str>>();
+
+      if numbers.len() != width {
+        return Err(ParseBingoError::RaggedRow {
+          card: index,
+          row,
+          expected: width,
+          found: numbers.len(),
+        });
+      }
 
-    Ok(Card {
+      for num in numbers {
+        cells.push(match num {
+          "FREE" => Cell::FREE,
+          _ => Cell::new(
+            u32::from_str(num).map_err(|_e| ParseBingoError::InvalidNumber {
+              card: index,
+              row,
+              value: String::from(num),
+            })?,
+          ),
+        });
+      }
+    }
+
+    Ok(Card::new(width, lines.len(), cells))
+  }
+
+  fn new(width: usize, height: usize, cells: Vec<Cell>) -> Card {
+    let mut positions: HashMap<u32, Vec<usize>> = HashMap::new();
+
+    for (i, cell) in cells.iter().enumerate() {
+      if let (Some(number), false) = (cell.number, cell.marked) {
+        positions.entry(number).or_default().push(i);
+      }
+    }
+
+    let mut card = Card {
       width,
-      numbers: lines
-        .flat_map(|line| {
-          line
-            .split_whitespace()
-            .map(|num| (u8::from_str(num).unwrap(), false))
+      height,
+      cells: cells
+        .iter()
+        .map(|cell| Cell {
+          marked: false,
+          ..*cell
         })
         .collect(),
-    })
+      positions,
+      row_hits: vec![0; height],
+      column_hits: vec![0; width],
+      diagonal_hits: 0,
+      anti_diagonal_hits: 0,
+      marked: 0,
+      winning_cells: Vec::new(),
+    };
+
+    for (i, cell) in cells.iter().enumerate() {
+      if cell.marked {
+        card.hit(i);
+      }
+    }
+
+    card
   }
-}
 
-impl Card {
-  fn mark(&mut self, number: u8) {
-    if self.numbers.contains_key(&number) {
-      self.numbers.insert(number, true);
+  // marks every cell holding the number, returning the first winning line
+  // through one of them
+  fn mark(&mut self, number: u32, rules: &Rules) -> Option<WinningLine> {
+    let positions = self.positions.remove(&number)?;
+
+    positions.iter().for_each(|&i| self.hit(i));
+    let line = positions.iter().find_map(|&i| self.win_through(i, rules));
+
+    if let (Some(line), true) = (line, self.winning_cells.is_empty()) {
+      self.winning_cells = self.line_cells(line, rules);
+    }
+
+    line
+  }
+
+  fn hit(&mut self, i: usize) {
+    let (row, col) = (i / self.width, i % self.width);
+
+    self.cells[i].marked = true;
+    self.row_hits[row] += 1;
+    self.column_hits[col] += 1;
+    self.marked += 1;
+
+    if self.width == self.height {
+      if row == col {
+        self.diagonal_hits += 1;
+      }
+
+      if row + col == self.width - 1 {
+        self.anti_diagonal_hits += 1;
+      }
+    }
+  }
+
+  // only cards with odd dimensions have a centre cell
+  fn free_centre(&mut self) {
+    if self.width % 2 == 1 && self.height % 2 == 1 {
+      let i = (self.height / 2) * self.width + self.width / 2;
+
+      if let Some(positions) = self.cells[i]
+        .number
+        .and_then(|number| self.positions.get_mut(&number))
+      {
+        positions.retain(|&p| p != i);
+      }
+
+      if !self.cells[i].marked {
+        self.hit(i);
+      }
+
+      self.cells[i] = Cell::FREE;
     }
   }
 
   fn marked_at_index(&self, i: usize) -> bool {
+    self.cells.get(i).is_some_and(|cell| cell.marked)
+  }
+
+  fn corners(&self) -> [usize; 4] {
+    let last = self.cells.len() - 1;
+    [0, self.width - 1, last + 1 - self.width, last]
+  }
+
+  // a mask only applies to cards with the same dimensions
+  fn mask_bingo(&self, width: usize, mask: &[bool]) -> bool {
+    width == self.width
+      && mask.len() == self.cells.len()
+      && mask
+        .iter()
+        .enumerate()
+        .all(|(i, required)| !required || self.marked_at_index(i))
+  }
+
+  // everything but masks is O(1); a mask is only rescanned when the cell
+  // is part of it
+  fn win_through(&self, i: usize, rules: &Rules) -> Option<WinningLine> {
+    let (row, col) = (i / self.width, i % self.width);
+    let square = self.width == self.height;
+
+    rules
+      .patterns
+      .iter()
+      .enumerate()
+      .find_map(|(p, pattern)| match pattern {
+        Pattern::Rows => (self.row_hits[row] == self.width).then_some(WinningLine::Row(row)),
+        Pattern::Columns => {
+          (self.column_hits[col] == self.height).then_some(WinningLine::Column(col))
+        }
+        Pattern::Diagonals if square && row == col && self.diagonal_hits == self.width => {
+          Some(WinningLine::Diagonal)
+        }
+        Pattern::Diagonals
+          if square && row + col == self.width - 1 && self.anti_diagonal_hits == self.width =>
+        {
+          Some(WinningLine::AntiDiagonal)
+        }
+        Pattern::Diagonals => None,
+        Pattern::FourCorners => {
+          let corners = self.corners();
+          (corners.contains(&i) && corners.iter().all(|&c| self.marked_at_index(c)))
+            .then_some(WinningLine::FourCorners)
+        }
+        Pattern::Blackout => (self.marked == self.cells.len()).then_some(WinningLine::Blackout),
+        Pattern::Mask { width, cells } => (cells.get(i) == Some(&true)
+          && self.mask_bingo(*width, cells))
+        .then_some(WinningLine::Mask(p)),
+      })
+  }
+
+  fn line_cells(&self, line: WinningLine, rules: &Rules) -> Vec<usize> {
+    let width = self.width;
+
+    match line {
+      WinningLine::Row(row) => (row * width..(row + 1) * width).collect(),
+      WinningLine::Column(col) => (0..self.height).map(|row| row * width + col).collect(),
+      WinningLine::Diagonal => (0..width).map(|i| i * width + i).collect(),
+      WinningLine::AntiDiagonal => (0..width).map(|i| i * width + width - 1 - i).collect(),
+      WinningLine::FourCorners => self.corners().to_vec(),
+      WinningLine::Blackout => (0..self.cells.len()).collect(),
+      WinningLine::Mask(p) => match &rules.patterns[p] {
+        Pattern::Mask { cells, .. } => (0..cells.len()).filter(|&i| cells[i]).collect(),
+        _ => vec![],
+      },
+    }
+  }
+
+  // the numbers on every line that could win the card under the rules,
+  // ignoring free spaces. lines made only of free spaces can't be completed
+  // by a draw, so they are skipped
+  fn candidate_lines(&self, rules: &Rules) -> Vec<Vec<u32>> {
+    let square = self.width == self.height;
+    let mut lines = Vec::new();
+
+    for (p, pattern) in rules.patterns.iter().enumerate() {
+      match pattern {
+        Pattern::Rows => lines.extend((0..self.height).map(WinningLine::Row)),
+        Pattern::Columns => lines.extend((0..self.width).map(WinningLine::Column)),
+        Pattern::Diagonals if square => {
+          lines.extend([WinningLine::Diagonal, WinningLine::AntiDiagonal])
+        }
+        Pattern::FourCorners if !self.cells.is_empty() => lines.push(WinningLine::FourCorners),
+        Pattern::Blackout => lines.push(WinningLine::Blackout),
+        Pattern::Mask { width, cells }
+          if *width == self.width && cells.len() == self.cells.len() =>
+        {
+          lines.push(WinningLine::Mask(p))
+        }
+        _ => (),
+      }
+    }
+
+    lines
+      .into_iter()
+      .map(|line| {
+        self
+          .line_cells(line, rules)
+          .into_iter()
+          .filter_map(|i| self.cells[i].number)
+          .collect::<Vec<u32>>()
+      })
+      .filter(|numbers| !numbers.is_empty())
+      .collect()
+  }
+
+  fn unmarked(&self) -> impl Iterator<Item = u32> + '_ {
     self
-      .numbers
-      .get_index(i.into())
-      .map_or(false, |(_n, marked)| *marked)
+      .cells
+      .iter()
+      .filter(|cell| !cell.marked)
+      .filter_map(|cell| cell.number)
   }
 
-  fn horizontal_bingo(&self) -> bool {
-    let mut row = 0;
+  // sum of the unmarked numbers times the winning number. u32 numbers can
+  // overflow a u64 score, but not a u128 one
+  fn score(&self, number: u32) -> u128 {
+    self.unmarked().map(|n| n as u128).sum::<u128>() * number as u128
+  }
+}
 
-    while row < (self.numbers.len() as u8 - self.width) {
-      let bingo = (row..row + self.width).all(|i| self.marked_at_index(i.into()));
+// marked numbers are [bracketed] and the first winning line is *starred*;
+// the alternate form (`{:#}`) uses ANSI bold and reverse video instead
+impl Display for Card {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let text = |cell: &Cell| cell.number.map_or(String::from("FREE"), |n| n.to_string());
+    let width = self
+      .cells
+      .iter()
+      .map(|cell| text(cell).len())
+      .max()
+      .unwrap_or(0);
 
-      if bingo {
-        return true;
-      } else {
-        row += self.width;
+    for (row, cells) in self.cells.chunks(self.width.max(1)).enumerate() {
+      if row > 0 {
+        writeln!(f)?;
+      }
+
+      for (col, cell) in cells.iter().enumerate() {
+        let winning = self.winning_cells.contains(&(row * self.width + col));
+        let (open, close) = match (winning, cell.marked, f.alternate()) {
+          (true, _, false) => ("*", "*"),
+          (true, _, true) => (" \x1b[1;7m", "\x1b[0m "),
+          (false, true, false) => ("[", "]"),
+          (false, true, true) => (" \x1b[1m", "\x1b[0m "),
+          (false, false, _) => (" ", " "),
+        };
+
+        if col > 0 {
+          f.write_str(" ")?;
+        }
+
+        write!(f, "{}{:>width$}{}", open, text(cell), close, width = width)?;
       }
     }
 
-    false
+    Ok(())
   }
+}
 
-  fn vertical_bingo(&self) -> bool {
-    let mut col = 0;
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum WinningLine {
+  Row(usize),
+  Column(usize),
+  Diagonal,
+  AntiDiagonal,
+  FourCorners,
+  Blackout,
+  // index of the mask in the rules' patterns
+  Mask(usize),
+}
 
-    while col < self.width {
-      // assumption: bingo cards are always square, so we can use width
-      // for the vertical offset
-      let bingo = (0..self.width).all(|i| self.marked_at_index(((i * self.width) + col).into()));
+impl Display for WinningLine {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      WinningLine::Row(row) => write!(f, "row {}", row),
+      WinningLine::Column(col) => write!(f, "column {}", col),
+      WinningLine::Diagonal => f.write_str("diagonal"),
+      WinningLine::AntiDiagonal => f.write_str("anti-diagonal"),
+      WinningLine::FourCorners => f.write_str("four corners"),
+      WinningLine::Blackout => f.write_str("blackout"),
+      WinningLine::Mask(i) => write!(f, "mask {}", i),
+    }
+  }
+}
 
-      if bingo {
-        return true;
-      } else {
-        col += 1;
+#[derive(PartialEq, Debug, Clone)]
+pub enum Pattern {
+  Rows,
+  Columns,
+  Diagonals,
+  FourCorners,
+  Blackout,
+  // cells in row-major order, true where the cell must be marked
+  Mask { width: usize, cells: Vec<bool> },
+}
+
+impl FromStr for Pattern {
+  type Err = ParseArgsError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "rows" => Ok(Pattern::Rows),
+      "columns" => Ok(Pattern::Columns),
+      "diagonals" => Ok(Pattern::Diagonals),
+      "corners" => Ok(Pattern::FourCorners),
+      "blackout" => Ok(Pattern::Blackout),
+      _ => Err(ParseArgsError(format!("unknown pattern: {}", s))),
+    }
+  }
+}
+
+impl Pattern {
+  // masks are grids of `#` (must be marked) and `.` (ignored), separated by
+  // blank lines, e.g. an X on a 3x3 card:
+  //
+  // #.#
+  // .#.
+  // #.#
+  fn parse_masks(s: &str) -> Result<Vec<Pattern>, ParseArgsError> {
+    let lines = s.lines().map(str::trim).collect::<Vec<&str>>();
+
+    lines
+      .split(|line| line.is_empty())
+      .filter(|block| !block.is_empty())
+      .map(|block| {
+        let width = block[0].len();
+        let mut cells = Vec::new();
+
+        for line in block {
+          if line.len() != width {
+            return Err(ParseArgsError(format!("ragged mask row: {}", line)));
+          }
+
+          for c in line.chars() {
+            match c {
+              '#' => cells.push(true),
+              '.' => cells.push(false),
+              _ => return Err(ParseArgsError(format!("invalid mask cell: {}", c))),
+            }
+          }
+        }
+
+        Ok(Pattern::Mask { width, cells })
+      })
+      .collect()
+  }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Rules {
+  patterns: Vec<Pattern>,
+  free_centre: bool,
+}
+
+impl Default for Rules {
+  fn default() -> Self {
+    Rules {
+      patterns: vec![Pattern::Rows, Pattern::Columns],
+      free_centre: false,
+    }
+  }
+}
+
+impl Rules {
+  // --patterns rows,columns,diagonals,corners,blackout (default rows,columns)
+  // --mask FILE (repeatable, adds every mask in the file)
+  // --free-centre (replaces the centre cell of every card with a free space)
+  pub fn from_args(args: impl Iterator<Item = String>) -> Result<Rules, ParseArgsError> {
+    let mut patterns = None;
+    let mut free_centre = false;
+    let mut masks = Vec::new();
+    let mut options = Options::new(args);
+
+    while let Some(option) = options.next_option() {
+      match option.as_str() {
+        "--patterns" => {
+          patterns = Some(
+            options
+              .value()?
+              .split(',')
+              .map(Pattern::from_str)
+              .collect::<Result<Vec<Pattern>, ParseArgsError>>()?,
+          )
+        }
+        "--mask" => {
+          let path = options.value()?;
+          let contents =
+            fs::read_to_string(&path).map_err(|e| ParseArgsError(format!("{}: {}", path, e)))?;
+          masks.extend(Pattern::parse_masks(&contents)?);
+        }
+        "--free-centre" => free_centre = true,
+        _ => return Err(options.unknown()),
       }
     }
 
-    false
+    let mut patterns = match patterns {
+      Some(patterns) => patterns,
+      None if !masks.is_empty() => vec![],
+      None => Rules::default().patterns,
+    };
+    patterns.extend(masks);
+
+    Ok(Rules {
+      patterns,
+      free_centre,
+    })
   }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ParseBingoError {
+  MissingDraws,
+  InvalidDraw(String),
+  InvalidNumber {
+    card: usize,
+    row: usize,
+    value: String,
+  },
+  RaggedRow {
+    card: usize,
+    row: usize,
+    expected: usize,
+    found: usize,
+  },
+}
 
-  fn bingo(&self) -> bool {
-    self.horizontal_bingo() || self.vertical_bingo()
+impl Display for ParseBingoError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ParseBingoError::MissingDraws => f.write_str("missing draw list"),
+      ParseBingoError::InvalidDraw(value) => write!(f, "invalid draw: {:?}", value),
+      ParseBingoError::InvalidNumber { card, row, value } => {
+        write!(f, "card {}, row {}: invalid number {:?}", card, row, value)
+      }
+      ParseBingoError::RaggedRow {
+        card,
+        row,
+        expected,
+        found,
+      } => write!(
+        f,
+        "card {}, row {}: expected {} numbers, found {}",
+        card, row, expected, found
+      ),
+    }
+  }
+}
+
+// the draw list is the first non-blank line, followed by cards separated by
+// one or more blank lines. CRLF line endings are accepted
+fn parse_input(input: &[String], rules: &Rules) -> Result<(Vec<u32>, Vec<Card>), ParseBingoError> {
+  let lines = input
+    .iter()
+    .map(|line| line.trim())
+    .skip_while(|line| line.is_empty())
+    .collect::<Vec<&str>>();
+
+  let (draws, rest) = lines.split_first().ok_or(ParseBingoError::MissingDraws)?;
+
+  let numbers = draws
+    .split(',')
+    .map(|x| u32::from_str(x.trim()).map_err(|_e| ParseBingoError::InvalidDraw(String::from(x))))
+    .collect::<Result<Vec<u32>, ParseBingoError>>()?;
+
+  let mut cards = rest
+    .split(|line| line.is_empty())
+    .filter(|lines| !lines.is_empty())
+    .enumerate()
+    .map(|(i, lines)| Card::parse(i, lines))
+    .collect::<Result<Vec<Card>, ParseBingoError>>()?;
+
+  if rules.free_centre {
+    cards.iter_mut().for_each(Card::free_centre);
+  }
+
+  Ok((numbers, cards))
+}
+
+#[derive(PartialEq, Debug)]
+struct Win {
+  card: usize,
+  // index into the draw list
+  draw: usize,
+  number: u32,
+  line: WinningLine,
+  score: u128,
+}
+
+// plays the whole draw list once, recording every card's win in order
+pub struct Game {
+  cards: usize,
+  wins: Vec<Win>,
+}
+
+impl Game {
+  fn play(numbers: &[u32], mut cards: Vec<Card>, rules: &Rules) -> Game {
+    let mut won = vec![false; cards.len()];
+    let mut wins = Vec::new();
+
+    for (draw, &number) in numbers.iter().enumerate() {
+      for (i, card) in cards.iter_mut().enumerate() {
+        let line = card.mark(number, rules);
+
+        if won[i] {
+          continue;
+        }
+
+        if let Some(line) = line {
+          won[i] = true;
+          wins.push(Win {
+            card: i,
+            draw,
+            number,
+            line,
+            score: card.score(number),
+          });
+        }
+      }
+    }
+
+    Game {
+      cards: cards.len(),
+      wins,
+    }
+  }
+
+  pub fn new(input: &[String], rules: &Rules) -> Result<Game, ParseBingoError> {
+    let (numbers, cards) = parse_input(input, rules)?;
+    Ok(Game::play(&numbers, cards, rules))
+  }
+
+  fn first_winner(&self) -> Option<&Win> {
+    self.wins.first()
+  }
+
+  fn last_winner(&self) -> Option<&Win> {
+    self.wins.last()
+  }
+
+  // 0-based, like Iterator::nth
+  fn nth_winner(&self, n: usize) -> Option<&Win> {
+    self.wins.get(n)
+  }
+
+  fn losers(&self) -> impl Iterator<Item = usize> + '_ {
+    (0..self.cards).filter(|card| !self.wins.iter().any(|win| win.card == *card))
+  }
+}
+
+impl Display for Game {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for win in &self.wins {
+      writeln!(
+        f,
+        "draw {} ({}): card {} wins on {}, score {}",
+        win.draw, win.number, win.card, win.line, win.score
+      )?;
+    }
+
+    for card in self.losers() {
+      writeln!(f, "card {} never wins", card)?;
+    }
+
+    Ok(())
   }
+}
 
-  fn unmarked(&self) -> impl Iterator<Item = u8> + '_ {
+// works out win draws from each card's candidate lines instead of replaying
+// the game: a line completes on the latest draw of any of its numbers, and a
+// card wins on the earliest of its lines
+pub struct Analysis {
+  draws: Vec<u32>,
+  lines: Vec<Vec<Vec<u32>>>,
+  // where each number is drawn, in order
+  positions: HashMap<u32, Vec<usize>>,
+  // the first draw of each number in each candidate line, and the draw the
+  // line completes on
+  times: Vec<Vec<Vec<Option<usize>>>>,
+  line_draws: Vec<Vec<Option<usize>>>,
+  // the (card, line) pairs each number appears in
+  holders: HashMap<u32, Vec<(usize, usize)>>,
+}
+
+#[derive(PartialEq, Debug)]
+struct Swap {
+  // indexes into the draw list
+  a: usize,
+  b: usize,
+  // the draw the card wins on after the swap
+  draw: usize,
+}
+
+// None if one of the line's numbers is never drawn
+fn line_draw(mut times: impl Iterator<Item = Option<usize>>) -> Option<usize> {
+  times
+    .try_fold(None, |latest: Option<usize>, time| {
+      time.map(|draw| latest.max(Some(draw)))
+    })
+    .flatten()
+}
+
+impl Analysis {
+  fn new(draws: Vec<u32>, cards: &[Card], rules: &Rules) -> Analysis {
+    let lines = cards
+      .iter()
+      .map(|card| card.candidate_lines(rules))
+      .collect::<Vec<_>>();
+
+    let mut positions: HashMap<u32, Vec<usize>> = HashMap::new();
+
+    for (i, number) in draws.iter().enumerate() {
+      positions.entry(*number).or_default().push(i);
+    }
+
+    let mut holders: HashMap<u32, Vec<(usize, usize)>> = HashMap::new();
+
+    for (card, card_lines) in lines.iter().enumerate() {
+      for (line, numbers) in card_lines.iter().enumerate() {
+        for number in numbers {
+          let holder = holders.entry(*number).or_default();
+
+          if holder.last() != Some(&(card, line)) {
+            holder.push((card, line));
+          }
+        }
+      }
+    }
+
+    let times = lines
+      .iter()
+      .map(|card_lines| {
+        card_lines
+          .iter()
+          .map(|line| {
+            line
+              .iter()
+              .map(|n| positions.get(n).map(|p| p[0]))
+              .collect::<Vec<_>>()
+          })
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+
+    let line_draws = times
+      .iter()
+      .map(|card_times| {
+        card_times
+          .iter()
+          .map(|line| line_draw(line.iter().copied()))
+          .collect()
+      })
+      .collect();
+
+    Analysis {
+      draws,
+      lines,
+      positions,
+      times,
+      line_draws,
+      holders,
+    }
+  }
+
+  // None for cards that can never win. that only depends on which numbers
+  // are drawn, not their order, so no swap can change it
+  fn win_draws(&self) -> Vec<Option<usize>> {
     self
-      .numbers
+      .line_draws
+      .iter()
+      .map(|lines| lines.iter().flatten().min().copied())
+      .collect()
+  }
+
+  // cards that win on the same draw are checked in order, so the lowest index
+  // wins first
+  fn first_winner(win_draws: &[Option<usize>]) -> Option<usize> {
+    win_draws
       .iter()
-      .filter_map(|(n, marked)| if *marked { None } else { Some(*n) })
+      .enumerate()
+      .filter_map(|(card, draw)| draw.map(|draw| (draw, card)))
+      .min()
+      .map(|(_draw, card)| card)
+  }
+
+  // the single swap of two draws that makes each card win first, winning as
+  // early as possible. None for the card that already wins first and for
+  // cards no swap helps. every swap is tried once and credited to the card it
+  // puts first. a swap only moves two numbers, so it only re-times the lines
+  // holding them, and the new first winner is either a card it touched or
+  // the leader among the rest
+  fn best_swaps(&self, win_draws: &[Option<usize>]) -> Vec<Option<Swap>> {
+    let first = Analysis::first_winner(win_draws);
+
+    let mut ranked = win_draws
+      .iter()
+      .enumerate()
+      .filter_map(|(card, draw)| draw.map(|draw| (draw, card)))
+      .collect::<Vec<_>>();
+    ranked.sort_unstable();
+
+    let mut line_draws = self.line_draws.clone();
+    let mut touched = vec![false; self.lines.len()];
+    let mut best: Vec<Option<Swap>> = (0..self.lines.len()).map(|_| None).collect();
+
+    for a in 0..self.draws.len() {
+      for b in a + 1..self.draws.len() {
+        let (x, y) = (self.draws[a], self.draws[b]);
+
+        if x == y {
+          continue;
+        }
+
+        let (xs, ys) = (&self.positions[&x], &self.positions[&y]);
+
+        // y moves up to draw a and x back to draw b, unless either is drawn
+        // somewhere else first
+        let first_drawn = |n: u32, time: Option<usize>| {
+          if n == y {
+            Some(ys[0].min(a))
+          } else if n == x && xs[0] == a {
+            Some(xs.get(1).map_or(b, |next| b.min(*next)))
+          } else {
+            time
+          }
+        };
+
+        let holders = [x, y]
+          .iter()
+          .filter_map(|n| self.holders.get(n))
+          .flatten()
+          .copied()
+          .collect::<Vec<_>>();
+        let mut cards = vec![];
+
+        for &(c, line) in &holders {
+          let numbers = self.lines[c][line].iter();
+          let times = numbers.zip(&self.times[c][line]);
+          line_draws[c][line] = line_draw(times.map(|(n, time)| first_drawn(*n, *time)));
+
+          if !touched[c] {
+            touched[c] = true;
+            cards.push(c);
+          }
+        }
+
+        let leader = cards
+          .iter()
+          .filter_map(|&c| line_draws[c].iter().flatten().min().map(|draw| (*draw, c)))
+          .chain(ranked.iter().find(|(_draw, c)| !touched[*c]).copied())
+          .min();
+
+        if let Some((draw, card)) = leader.filter(|(_draw, card)| Some(*card) != first) {
+          if best[card].as_ref().is_none_or(|best| draw < best.draw) {
+            best[card] = Some(Swap { a, b, draw });
+          }
+        }
+
+        for &(c, line) in &holders {
+          line_draws[c][line] = self.line_draws[c][line];
+          touched[c] = false;
+        }
+      }
+    }
+
+    best
   }
 }
 
-fn parse_input(input: &[String]) -> (Vec<u8>, Vec<Card>) {
-  let numbers: Vec<u8> = input
-    .iter()
-    .take(1)
-    .flat_map(|nums| {
-      nums
-        .split(",")
-        .map(|x| u8::from_str(x).unwrap())
-        .collect::<Vec<u8>>()
-    })
-    .collect();
+impl Display for Analysis {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let win_draws = self.win_draws();
+    let first = Analysis::first_winner(&win_draws);
+    let swaps = self.best_swaps(&win_draws);
+
+    for (card, draw) in win_draws.iter().enumerate() {
+      match draw {
+        None => writeln!(f, "card {} never wins", card)?,
+        Some(draw) if first == Some(card) => writeln!(
+          f,
+          "card {} wins first on draw {} ({})",
+          card, draw, self.draws[*draw]
+        )?,
+        Some(draw) => {
+          write!(
+            f,
+            "card {} wins on draw {} ({}); ",
+            card, draw, self.draws[*draw]
+          )?;
+
+          match &swaps[card] {
+            Some(swap) => writeln!(
+              f,
+              "swapping draws {} and {} makes it win first on draw {}",
+              swap.a, swap.b, swap.draw
+            )?,
+            None => writeln!(f, "no single swap makes it win first")?,
+          }
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+pub fn analyse(input: &[String], rules: &Rules) -> Result<Analysis, ParseBingoError> {
+  let (numbers, cards) = parse_input(input, rules)?;
+  Ok(Analysis::new(numbers, &cards, rules))
+}
+
+pub fn part1(input: &[String], rules: &Rules) -> Result<u128, ParseBingoError> {
+  Ok(
+    Game::new(input, rules)?
+      .first_winner()
+      .map_or(0, |win| win.score),
+  )
+}
 
-  let cards: Vec<Card> = input
-    .split_at(2)
-    .1
-    .split(|s| s.trim() == "")
-    .map(|s| Card::from_str(&*s.join("\n")).unwrap())
-    .collect();
+pub fn part2(input: &[String], rules: &Rules) -> Result<u128, ParseBingoError> {
+  Ok(
+    Game::new(input, rules)?
+      .last_winner()
+      .map_or(0, |win| win.score),
+  )
+}
 
-  (numbers, cards)
+// score of the nth card to win (0-based)
+pub fn nth(input: &[String], rules: &Rules, n: usize) -> Result<u128, ParseBingoError> {
+  Ok(
+    Game::new(input, rules)?
+      .nth_winner(n)
+      .map_or(0, |win| win.score),
+  )
 }
 
-pub fn part1(input: &[String]) -> u32 {
-  let (numbers, mut cards) = parse_input(input);
+// prints every card after each draw. on a terminal the screen is redrawn
+// with ANSI highlighting, pausing between draws
+pub fn watch(input: &[String], rules: &Rules) -> Result<(), ParseBingoError> {
+  let (numbers, mut cards) = parse_input(input, rules)?;
+  let terminal = io::stdout().is_terminal();
 
-  for number in numbers {
+  for (draw, &number) in numbers.iter().enumerate() {
     for card in cards.iter_mut() {
-      card.mark(number);
+      card.mark(number, rules);
+    }
+
+    if terminal {
+      print!("\x1b[2J\x1b[H");
+    }
+
+    println!("draw {} ({})", draw, number);
 
-      if card.bingo() {
-        let result: u32 = card.unmarked().map(|x| x as u32).sum();
-        return number as u32 * result;
+    for (i, card) in cards.iter().enumerate() {
+      if terminal {
+        println!("\ncard {}\n{:#}", i, card);
+      } else {
+        println!("\ncard {}\n{}", i, card);
       }
     }
+
+    if terminal {
+      thread::sleep(Duration::from_millis(500));
+    } else {
+      println!();
+    }
   }
 
-  return 0;
+  Ok(())
 }
 
-pub fn part2(input: &[String]) -> u32 {
-  let (numbers, mut cards) = parse_input(input);
-  let card_count = cards.len();
-  let mut winning_card_indexes = HashSet::new();
+// random cards and draws in the format parse_input reads. numbers are
+// distinct within a card, and the draw list is a shuffle of the range
+// (or the first `draws` numbers of one)
+#[derive(PartialEq, Debug)]
+pub struct Generator {
+  cards: usize,
+  width: usize,
+  height: usize,
+  min: u32,
+  max: u32,
+  draws: Option<usize>,
+  seed: u64,
+}
+
+impl Default for Generator {
+  fn default() -> Self {
+    Generator {
+      cards: 100,
+      width: 5,
+      height: 5,
+      min: 0,
+      max: 99,
+      draws: None,
+      seed: 0,
+    }
+  }
+}
 
-  for number in numbers {
-    for (i, card) in cards.iter_mut().enumerate() {
-      card.mark(number);
+impl Generator {
+  // --cards N (default 100)
+  // --size WIDTHxHEIGHT or --size N for square cards (default 5)
+  // --range MIN-MAX (default 0-99)
+  // --draws N (default the whole range)
+  // --seed N (default 0)
+  pub fn from_args(args: impl Iterator<Item = String>) -> Result<Generator, ParseArgsError> {
+    let mut generator = Generator::default();
+    let mut options = Options::new(args);
 
-      if card.bingo() && !winning_card_indexes.contains(&i) {
-        winning_card_indexes.insert(i);
+    while let Some(option) = options.next_option() {
+      match option.as_str() {
+        "--cards" => generator.cards = options.parse()?,
+        "--size" => {
+          let value = options.value()?;
+          let (width, height) = value.split_once('x').unwrap_or((&value, &value));
+          generator.width = width.parse().map_err(|_e| options.invalid(&value))?;
+          generator.height = height.parse().map_err(|_e| options.invalid(&value))?;
 
-        if winning_card_indexes.len() == card_count {
-          let result: u32 = card.unmarked().map(|x| x as u32).sum();
-          return number as u32 * result;
+          // empty cards would come out as blank lines, which read back as no cards
+          if generator.width == 0 || generator.height == 0 {
+            return Err(options.invalid(&value));
+          }
         }
+        "--range" => (generator.min, generator.max) = options.pair('-')?,
+        "--draws" => generator.draws = Some(options.parse()?),
+        "--seed" => generator.seed = options.parse()?,
+        _ => return Err(options.unknown()),
       }
     }
+
+    let range = (generator.max as u64 + 1).saturating_sub(generator.min as u64);
+    let too_small = |what: String| {
+      Err(ParseArgsError(format!(
+        "range {}-{} is too small for {}",
+        generator.min, generator.max, what
+      )))
+    };
+
+    if range == 0 {
+      too_small(String::from("anything"))
+    } else if (generator.width * generator.height) as u64 > range {
+      too_small(format!("{}x{} cards", generator.width, generator.height))
+    } else if let Some(draws) = generator.draws.filter(|&draws| draws as u64 > range) {
+      too_small(format!("{} draws", draws))
+    } else {
+      Ok(generator)
+    }
   }
 
-  return 0;
+  pub fn generate(&self) -> String {
+    let mut rng = Rng(self.seed);
+    let draw_count = self.draws.unwrap_or((self.max - self.min) as usize + 1);
+    let digits = self.max.to_string().len();
+
+    let draws = rng
+      .sample(self.min, self.max, draw_count)
+      .iter()
+      .map(|n| n.to_string())
+      .collect::<Vec<String>>()
+      .join(",");
+
+    let cards = (0..self.cards).map(|_card| {
+      rng
+        .sample(self.min, self.max, self.width * self.height)
+        .chunks(self.width.max(1))
+        .map(|row| {
+          row
+            .iter()
+            .map(|n| format!("{:>digits$}", n, digits = digits))
+            .collect::<Vec<String>>()
+            .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+    });
+
+    std::iter::once(draws)
+      .chain(cards)
+      .collect::<Vec<String>>()
+      .join("\n\n")
+      + "\n"
+  }
 }
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -180,19 +1095,32 @@ mod tests {
 22 11 13  6  5
  2  0 12  3  7";
 
+  // marks each number in turn, returning the result of the last mark
+  fn mark_all(card: &mut Card, numbers: &[u32], rules: &Rules) -> Option<WinningLine> {
+    numbers
+      .iter()
+      .fold(None, |_line, &number| card.mark(number, rules))
+  }
+
   #[test]
   fn example_part1() {
     assert_eq!(
-      part1(&EXAMPLE.lines().map(String::from).collect::<Vec<String>>()),
-      4512
+      part1(
+        &EXAMPLE.lines().map(String::from).collect::<Vec<String>>(),
+        &Rules::default()
+      ),
+      Ok(4512)
     )
   }
 
   #[test]
   fn example_part2() {
     assert_eq!(
-      part2(&EXAMPLE.lines().map(String::from).collect::<Vec<String>>()),
-      1924
+      part2(
+        &EXAMPLE.lines().map(String::from).collect::<Vec<String>>(),
+        &Rules::default()
+      ),
+      Ok(1924)
     )
   }
 
@@ -204,15 +1132,19 @@ mod tests {
             7 8 9",
     );
 
-    let mut expected_squares: IndexMap<u8, bool> = IndexMap::new();
-
-    (1..10).for_each(|x| drop(expected_squares.insert(x, false)));
-
     assert_eq!(
       card.unwrap(),
       Card {
         width: 3,
-        numbers: expected_squares
+        height: 3,
+        cells: (1..10).map(Cell::new).collect(),
+        positions: (1..10).map(|n| (n, vec![n as usize - 1])).collect(),
+        row_hits: vec![0; 3],
+        column_hits: vec![0; 3],
+        diagonal_hits: 0,
+        anti_diagonal_hits: 0,
+        marked: 0,
+        winning_cells: vec![],
       }
     )
   }
@@ -226,9 +1158,8 @@ mod tests {
     )
     .unwrap();
 
-    assert!(!card.horizontal_bingo());
-    vec![4, 5, 6].iter().for_each(|x: &u8| card.mark(*x));
-    assert!(card.horizontal_bingo());
+    assert_eq!(mark_all(&mut card, &[4, 5], &Rules::default()), None);
+    assert_eq!(card.mark(6, &Rules::default()), Some(WinningLine::Row(1)));
   }
 
   #[test]
@@ -240,9 +1171,11 @@ mod tests {
     )
     .unwrap();
 
-    assert!(!card.vertical_bingo());
-    vec![1, 4, 7].iter().for_each(|x: &u8| card.mark(*x));
-    assert!(card.vertical_bingo());
+    assert_eq!(mark_all(&mut card, &[1, 4], &Rules::default()), None);
+    assert_eq!(
+      card.mark(7, &Rules::default()),
+      Some(WinningLine::Column(0))
+    );
   }
 
   #[test]
@@ -254,7 +1187,535 @@ mod tests {
     )
     .unwrap();
 
-    vec![1, 2, 3, 4].iter().for_each(|x: &u8| card.mark(*x));
-    assert_eq!(card.unmarked().collect::<Vec<u8>>(), vec![5, 6, 7, 8, 9])
+    mark_all(&mut card, &[1, 2, 3, 4], &Rules::default());
+    assert_eq!(card.unmarked().collect::<Vec<u32>>(), vec![5, 6, 7, 8, 9])
+  }
+
+  #[test]
+  fn last_row_bingo() {
+    let mut card = Card::from_str(
+      "1 2 3
+            4 5 6
+            7 8 9",
+    )
+    .unwrap();
+
+    assert_eq!(
+      mark_all(&mut card, &[7, 8, 9], &Rules::default()),
+      Some(WinningLine::Row(2))
+    );
+  }
+
+  #[test]
+  fn rectangular_card() {
+    let mut card = Card::from_str(
+      "1 2 3
+            4 5 6",
+    )
+    .unwrap();
+
+    assert_eq!((card.width, card.height), (3, 2));
+
+    assert_eq!(card.mark(2, &Rules::default()), None);
+    assert_eq!(
+      card.mark(5, &Rules::default()),
+      Some(WinningLine::Column(1))
+    );
+  }
+
+  #[test]
+  fn diagonal_bingo() {
+    let mut card = Card::from_str(
+      "1 2 3
+            4 5 6
+            7 8 9",
+    )
+    .unwrap();
+    let rules =
+      Rules::from_args(["--patterns", "diagonals"].map(String::from).into_iter()).unwrap();
+
+    assert_eq!(mark_all(&mut card, &[3, 5], &rules), None);
+    assert_eq!(card.mark(7, &rules), Some(WinningLine::AntiDiagonal));
+    assert_eq!(card.win_through(6, &Rules::default()), None);
+  }
+
+  #[test]
+  fn corners_and_blackout() {
+    let mut card = Card::from_str(
+      "1 2 3
+            4 5 6",
+    )
+    .unwrap();
+    let corners = Rules {
+      patterns: vec![Pattern::FourCorners],
+      ..Rules::default()
+    };
+    let blackout = Rules {
+      patterns: vec![Pattern::Blackout],
+      ..Rules::default()
+    };
+
+    assert_eq!(
+      mark_all(&mut card, &[1, 3, 4, 6], &corners),
+      Some(WinningLine::FourCorners)
+    );
+    assert_eq!(card.win_through(5, &blackout), None);
+    assert_eq!(
+      mark_all(&mut card, &[2, 5], &blackout),
+      Some(WinningLine::Blackout)
+    );
+  }
+
+  #[test]
+  fn mask_bingo() {
+    let masks = Pattern::parse_masks(
+      "#.#
+       .#.
+       #.#
+
+       ##",
+    )
+    .unwrap();
+
+    assert_eq!(
+      masks[1],
+      Pattern::Mask {
+        width: 2,
+        cells: vec![true, true]
+      }
+    );
+
+    let mut card = Card::from_str(
+      "1 2 3
+            4 5 6
+            7 8 9",
+    )
+    .unwrap();
+    let rules = Rules {
+      patterns: masks,
+      ..Rules::default()
+    };
+
+    assert_eq!(mark_all(&mut card, &[1, 3, 5, 7], &rules), None);
+    assert_eq!(card.mark(9, &rules), Some(WinningLine::Mask(0)));
+  }
+
+  #[test]
+  fn invalid_rules() {
+    assert!(Pattern::parse_masks("#.\n#").is_err());
+    assert!(Pattern::parse_masks("#x").is_err());
+    assert!(Rules::from_args(["--patterns", "stars"].map(String::from).into_iter()).is_err());
+    assert!(Rules::from_args(["--mask"].map(String::from).into_iter()).is_err());
+  }
+
+  #[test]
+  fn large_numbers_and_cards() {
+    let side = 20;
+    let grid = (0..side)
+      .map(|row| {
+        (0..side)
+          .map(|col| (1000 + row * side + col).to_string())
+          .collect::<Vec<String>>()
+          .join(" ")
+      })
+      .collect::<Vec<String>>()
+      .join("\n");
+    let draws = (0..side)
+      .map(|col| (1000 + (side - 1) * side + col).to_string())
+      .collect::<Vec<String>>()
+      .join(",");
+    let input = format!("{}\n\n{}", draws, grid)
+      .lines()
+      .map(String::from)
+      .collect::<Vec<String>>();
+
+    let unmarked: u128 = (1000..1000 + (side - 1) * side).map(|n| n as u128).sum();
+    let last = 1000 + side * side - 1;
+
+    assert_eq!(
+      part1(&input, &Rules::default()),
+      Ok(unmarked * last as u128)
+    );
+  }
+
+  #[test]
+  fn score_beyond_u64() {
+    let input = lines("4000000000,4000000001\n\n4000000000 4000000001\n4000000002 4000000003");
+
+    assert_eq!(
+      part1(&input, &Rules::default()),
+      Ok((4000000002 + 4000000003) * 4000000001)
+    );
+  }
+
+  #[test]
+  fn duplicate_numbers() {
+    let mut card = Card::from_str(
+      "1 2 1
+            4 5 6
+            7 8 9",
+    )
+    .unwrap();
+
+    assert_eq!(card.cells.len(), 9);
+    card.mark(1, &Rules::default());
+    assert!(card.marked_at_index(0) && card.marked_at_index(2));
+    assert!(!card.marked_at_index(1));
+    assert_eq!(card.mark(2, &Rules::default()), Some(WinningLine::Row(0)));
+  }
+
+  #[test]
+  fn free_spaces() {
+    let mut card = Card::from_str(
+      "1 2 3
+            4 FREE 6
+            7 8 9",
+    )
+    .unwrap();
+
+    assert!(card.marked_at_index(4));
+    assert_eq!(
+      mark_all(&mut card, &[4, 6], &Rules::default()),
+      Some(WinningLine::Row(1))
+    );
+    assert_eq!(
+      card.unmarked().collect::<Vec<u32>>(),
+      vec![1, 2, 3, 7, 8, 9]
+    );
+  }
+
+  #[test]
+  fn free_centre() {
+    let rules = Rules::from_args(["--free-centre"].map(String::from).into_iter()).unwrap();
+    let (_numbers, cards) = parse_input(
+      &EXAMPLE.lines().map(String::from).collect::<Vec<String>>(),
+      &rules,
+    )
+    .unwrap();
+
+    assert!(cards.iter().all(|card| card.marked_at_index(12)));
+    assert!(cards.iter().all(|card| card.row_hits[2] == 1));
+    assert_eq!(cards[0].unmarked().count(), 24);
+    assert!(!cards[0].unmarked().any(|n| n == 14));
+  }
+
+  #[test]
+  fn game_timeline() {
+    let game = Game::new(
+      &EXAMPLE.lines().map(String::from).collect::<Vec<String>>(),
+      &Rules::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+      game.first_winner(),
+      Some(&Win {
+        card: 2,
+        draw: 11,
+        number: 24,
+        line: WinningLine::Row(0),
+        score: 4512
+      })
+    );
+    assert_eq!(game.nth_winner(1).map(|win| win.card), Some(0));
+    assert_eq!(
+      game.last_winner().map(|win| (win.card, win.score)),
+      Some((1, 1924))
+    );
+    assert_eq!(game.nth_winner(3), None);
+    assert_eq!(game.losers().count(), 0);
+    assert_eq!(
+      game.to_string().lines().next(),
+      Some("draw 11 (24): card 2 wins on row 0, score 4512")
+    );
+  }
+
+  #[test]
+  fn display() {
+    let mut card = Card::from_str(
+      "1 2 3
+            4 FREE 6
+            7 8 10",
+    )
+    .unwrap();
+
+    assert_eq!(
+      mark_all(&mut card, &[1, 4, 6], &Rules::default()),
+      Some(WinningLine::Row(1))
+    );
+    assert_eq!(
+      card.to_string(),
+      "[   1]     2      3 \n*   4* *FREE* *   6*\n    7      8     10 "
+    );
+    assert!(format!("{:#}", card).contains("\x1b[1;7mFREE\x1b[0m"));
+  }
+
+  fn lines(s: &str) -> Vec<String> {
+    s.split('\n').map(String::from).collect()
+  }
+
+  #[test]
+  fn parse_crlf_and_blank_lines() {
+    let input = lines(&format!(
+      "\n\n{}\n\n\n",
+      EXAMPLE
+        .replace('\n', "\r\n")
+        .replace("\r\n\r\n", "\r\n\r\n\r\n")
+    ));
+    let (numbers, cards) = parse_input(&input, &Rules::default()).unwrap();
+
+    assert_eq!(numbers.len(), 27);
+    assert_eq!(cards.len(), 3);
+    assert_eq!(part1(&input, &Rules::default()), Ok(4512));
+  }
+
+  #[test]
+  fn parse_errors() {
+    assert_eq!(
+      parse_input(&lines("\n\n"), &Rules::default()).err(),
+      Some(ParseBingoError::MissingDraws)
+    );
+    assert_eq!(
+      parse_input(&lines("1,x,3"), &Rules::default()).err(),
+      Some(ParseBingoError::InvalidDraw(String::from("x")))
+    );
+
+    let error = parse_input(&lines("1,2\n\n1 2\n3 4\n\n1 2\n3\n4 5"), &Rules::default()).err();
+    assert_eq!(
+      error,
+      Some(ParseBingoError::RaggedRow {
+        card: 1,
+        row: 1,
+        expected: 2,
+        found: 1
+      })
+    );
+    assert_eq!(
+      error.unwrap().to_string(),
+      "card 1, row 1: expected 2 numbers, found 1"
+    );
+
+    assert_eq!(
+      parse_input(&lines("1\n\n1 2\n3 -4"), &Rules::default()).err(),
+      Some(ParseBingoError::InvalidNumber {
+        card: 0,
+        row: 1,
+        value: String::from("-4")
+      })
+    );
+  }
+
+  #[test]
+  fn analysis_matches_game() {
+    let input = EXAMPLE.lines().map(String::from).collect::<Vec<String>>();
+    let (numbers, cards) = parse_input(&input, &Rules::default()).unwrap();
+    let analysis = Analysis::new(numbers, &cards, &Rules::default());
+    let game = Game::new(&input, &Rules::default()).unwrap();
+    let win_draws = analysis.win_draws();
+
+    for win in &game.wins {
+      assert_eq!(win_draws[win.card], Some(win.draw));
+    }
+
+    assert_eq!(Analysis::first_winner(&win_draws), Some(2));
+    assert_eq!(analysis.best_swaps(&win_draws)[2], None);
+    assert!(win_draws.iter().all(Option::is_some));
+  }
+
+  #[test]
+  fn best_swap() {
+    let input = EXAMPLE.lines().map(String::from).collect::<Vec<String>>();
+    let (numbers, cards) = parse_input(&input, &Rules::default()).unwrap();
+    let analysis = Analysis::new(numbers.clone(), &cards, &Rules::default());
+    let swaps = analysis.best_swaps(&analysis.win_draws());
+
+    for card in [0, 1] {
+      let swap = swaps[card].as_ref().unwrap();
+      let mut draws = numbers.clone();
+      draws.swap(swap.a, swap.b);
+
+      let (_numbers, cards) = parse_input(&input, &Rules::default()).unwrap();
+      let game = Game::play(&draws, cards, &Rules::default());
+
+      assert_eq!(
+        game.first_winner().map(|win| (win.card, win.draw)),
+        Some((card, swap.draw))
+      );
+    }
+  }
+
+  // replays the game after every swap
+  fn brute_force_swaps(input: &[String], rules: &Rules) -> Vec<Option<Swap>> {
+    let (numbers, cards) = parse_input(input, rules).unwrap();
+    let play = |draws: &[u32]| {
+      let (_numbers, cards) = parse_input(input, rules).unwrap();
+      Game::play(draws, cards, rules)
+        .first_winner()
+        .map(|win| (win.card, win.draw))
+    };
+    let first = play(&numbers).map(|(card, _draw)| card);
+    let mut best: Vec<Option<Swap>> = (0..cards.len()).map(|_| None).collect();
+
+    for a in 0..numbers.len() {
+      for b in a + 1..numbers.len() {
+        let mut draws = numbers.clone();
+        draws.swap(a, b);
+
+        if let Some((card, draw)) = play(&draws).filter(|(card, _draw)| Some(*card) != first) {
+          if best[card].as_ref().is_none_or(|best| draw < best.draw) {
+            best[card] = Some(Swap { a, b, draw });
+          }
+        }
+      }
+    }
+
+    best
+  }
+
+  #[test]
+  fn best_swap_holds_a_rival_back() {
+    let rules = Rules {
+      patterns: vec![Pattern::Rows],
+      ..Rules::default()
+    };
+    let input = lines("5,1,2,3,4,9\n\n1 2\n\n3 4\n\n4 5");
+    let analysis = analyse(&input, &rules).unwrap();
+
+    assert_eq!(
+      analysis.best_swaps(&analysis.win_draws())[1],
+      Some(Swap {
+        a: 1,
+        b: 5,
+        draw: 4
+      })
+    );
+    assert_eq!(
+      analysis.best_swaps(&analysis.win_draws()),
+      brute_force_swaps(&input, &rules)
+    );
+  }
+
+  #[test]
+  fn best_swaps_match_brute_force() {
+    for seed in 0..300 {
+      let input = generate(&format!("--cards 4 --size 3 --range 0-11 --seed {}", seed));
+      let analysis = analyse(&input, &Rules::default()).unwrap();
+
+      assert_eq!(
+        analysis.best_swaps(&analysis.win_draws()),
+        brute_force_swaps(&input, &Rules::default()),
+        "seed {}",
+        seed
+      );
+    }
+  }
+
+  #[test]
+  fn never_wins() {
+    let input = lines("1,2,3,4\n\n1 2\n3 4\n\n1 5\n6 7\n\n1 6\n7 2");
+    let (numbers, cards) = parse_input(&input, &Rules::default()).unwrap();
+    let analysis = Analysis::new(numbers, &cards, &Rules::default());
+
+    assert_eq!(analysis.win_draws(), vec![Some(1), None, None]);
+    assert_eq!(analysis.best_swaps(&analysis.win_draws())[1], None);
+    assert_eq!(
+      analysis.to_string(),
+      "card 0 wins first on draw 1 (2)\ncard 1 never wins\ncard 2 never wins\n"
+    );
+  }
+
+  fn generate(args: &str) -> Vec<String> {
+    Generator::from_args(args.split_whitespace().map(String::from))
+      .unwrap()
+      .generate()
+      .lines()
+      .map(String::from)
+      .collect()
+  }
+
+  #[test]
+  fn generated_input_parses() {
+    let input = generate("--cards 20 --size 4x3 --range 100-199 --seed 7");
+    let (numbers, cards) = parse_input(&input, &Rules::default()).unwrap();
+
+    let mut sorted = numbers.clone();
+    sorted.sort();
+    assert_eq!(sorted, (100..200).collect::<Vec<u32>>());
+
+    assert_eq!(cards.len(), 20);
+
+    for card in &cards {
+      assert_eq!((card.width, card.height), (4, 3));
+
+      let mut card_numbers = card.unmarked().collect::<Vec<u32>>();
+      card_numbers.sort();
+      card_numbers.dedup();
+      assert_eq!(card_numbers.len(), 12);
+      assert!(card_numbers.iter().all(|n| (100..200).contains(n)));
+    }
+
+    // every number is drawn, so every card wins
+    assert_eq!(Game::new(&input, &Rules::default()).unwrap().wins.len(), 20);
+  }
+
+  #[test]
+  fn generator_is_seeded() {
+    assert_eq!(generate("--seed 1"), generate("--seed 1"));
+    assert_ne!(generate("--seed 1"), generate("--seed 2"));
+    assert_eq!(
+      generate("--cards 1 --size 2 --range 0-1000000000 --draws 3 --seed 3").len(),
+      4
+    );
+  }
+
+  #[test]
+  fn invalid_generator_args() {
+    let parse = |args: &str| Generator::from_args(args.split_whitespace().map(String::from));
+
+    assert!(parse("--size 5 --range 0-23").is_err());
+    assert!(parse("--range 10-5").is_err());
+    assert!(parse("--draws 101").is_err());
+    assert!(parse("--cards").is_err());
+    assert!(parse("--cards x").is_err());
+    assert!(parse("--size 0x5").is_err());
+    assert!(parse("--size 5x0").is_err());
+    assert!(parse("--size 0").is_err());
+  }
+
+  // run with `cargo test --release -- --ignored bench_analysis --nocapture`
+  #[test]
+  #[ignore]
+  fn bench_analysis() {
+    use std::time::Instant;
+
+    let input = generate("--cards 100 --size 5 --range 0-99 --seed 3");
+    let analysis = analyse(&input, &Rules::default()).unwrap();
+
+    let start = Instant::now();
+    let report = analysis.to_string();
+
+    println!(
+      "100 cards, 100 draws: {:?}, {} swaps found",
+      start.elapsed(),
+      report.matches("swapping").count()
+    );
+  }
+
+  // run with `cargo test --release -- --ignored bench_game --nocapture`
+  #[test]
+  #[ignore]
+  fn bench_game() {
+    use std::time::Instant;
+
+    let input = generate("--cards 10000 --size 5 --range 0-9999 --seed 1");
+
+    let start = Instant::now();
+    let game = Game::new(&input, &Rules::default()).unwrap();
+
+    println!(
+      "10000 cards, 10000 draws: {:?}, {} wins",
+      start.elapsed(),
+      game.wins.len()
+    );
   }
 }
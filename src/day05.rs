@@ -1,37 +1,119 @@
-use std::cmp::{max, min};
+use std::cmp::{max, min, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::iter;
 use std::str::FromStr;
 
-#[derive(Debug)]
-struct Point {
-    x: u16,
-    y: u16,
+// z is None for the puzzle's flat points. they lie on z = 0 and compare equal
+// to points written with an explicit 0, but still print without one.
+// coordinates are i32 rather than a type parameter: it covers the survey's
+// signed offsets, and the planes and the sweep already widen to i64 where
+// they need more room
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    x: i32,
+    y: i32,
+    z: Option<i32>,
+}
+
+impl PartialEq for Point {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Point {}
+
+impl Hash for Point {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
 }
 
 impl FromStr for Point {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let v: Vec<&str> = s.split(",").collect();
-        match v.as_slice() {
-            [x, y] => Ok(Point::new(
-                u16::from_str(x.trim()).unwrap(),
-                u16::from_str(y.trim()).unwrap(),
-            )),
+        let v = s
+            .split(",")
+            .map(|c| i32::from_str(c.trim()).map_err(|_| ()))
+            .collect::<Result<Vec<i32>, ()>>()?;
+
+        match *v.as_slice() {
+            [x, y] => Ok(Point::new(x, y)),
+            [x, y, z] => Ok(Point::new_3d(x, y, z)),
             _ => Err(()),
         }
     }
 }
 
+impl Display for Point {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.z {
+            None => write!(f, "{},{}", self.x, self.y),
+            Some(z) => write!(f, "{},{},{}", self.x, self.y, z),
+        }
+    }
+}
+
 impl Point {
-    fn new(x: u16, y: u16) -> Point {
-        Point { x, y }
+    fn new(x: i32, y: i32) -> Point {
+        Point { x, y, z: None }
+    }
+
+    fn new_3d(x: i32, y: i32, z: i32) -> Point {
+        Point { x, y, z: Some(z) }
+    }
+
+    fn depth(self) -> i32 {
+        self.z.unwrap_or(0)
+    }
+
+    fn key(self) -> (i32, i32, i32) {
+        (self.x, self.y, self.depth())
+    }
+
+    // componentwise minimum and maximum, for bounding boxes. the result only
+    // has a z if one of the points does
+    fn lower(self, other: Point) -> Point {
+        Point {
+            x: min(self.x, other.x),
+            y: min(self.y, other.y),
+            z: self.z.or(other.z).map(|_| min(self.depth(), other.depth())),
+        }
+    }
+
+    fn upper(self, other: Point) -> Point {
+        Point {
+            x: max(self.x, other.x),
+            y: max(self.y, other.y),
+            z: self.z.or(other.z).map(|_| max(self.depth(), other.depth())),
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct Line(Point, Point);
 
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {}", self.0, self.1)
+    }
+}
+
 impl FromStr for Line {
     type Err = ();
 
@@ -50,19 +132,145 @@ impl Line {
         self.0.y == self.1.y
     }
 
-    fn vertical(&self) -> bool {
-        self.0.x == self.1.x
+    fn kind(&self) -> LineKind {
+        let deltas = [
+            self.0.x.abs_diff(self.1.x),
+            self.0.y.abs_diff(self.1.y),
+            self.0.depth().abs_diff(self.1.depth()),
+        ];
+        let moving = deltas.into_iter().filter(|&d| d > 0).collect::<Vec<u32>>();
+
+        match moving.as_slice() {
+            [] | [_] => LineKind::Axis,
+            [first, rest @ ..] if rest.iter().all(|d| d == first) => LineKind::Diagonal,
+            _ => LineKind::General,
+        }
+    }
+
+    // Bresenham's algorithm, from the first point to the second, stepping
+    // along the axis that moves furthest. axis-aligned and 45° lines come out
+    // exact; other slopes get the closest lattice points. the points of a
+    // flat line are flat too
+    fn points(&self) -> impl Iterator<Item = Point> {
+        let flat = self.0.z.is_none() && self.1.z.is_none();
+        let start = [self.0.x, self.0.y, self.0.depth()].map(|v| v as i64);
+        let end = [self.1.x, self.1.y, self.1.depth()].map(|v| v as i64);
+        let deltas = [0, 1, 2].map(|i| (end[i] - start[i]).abs());
+        let steps = deltas.into_iter().max().unwrap();
+        let mut errors = deltas.map(|d| 2 * d - steps);
+        let mut position = start;
+        let mut remaining = steps + 1;
+
+        iter::from_fn(move || {
+            if remaining == 0 {
+                return None;
+            }
+
+            let point = Point {
+                x: position[0] as i32,
+                y: position[1] as i32,
+                z: (!flat).then_some(position[2] as i32),
+            };
+            remaining -= 1;
+
+            for i in 0..3 {
+                if errors[i] >= 0 {
+                    position[i] += (end[i] - start[i]).signum();
+                    errors[i] -= 2 * steps;
+                }
+                errors[i] += 2 * deltas[i];
+            }
+
+            Some(point)
+        })
+    }
+
+    fn covers(&self, point: Point) -> bool {
+        let (lo, hi) = (self.0.lower(self.1), self.0.upper(self.1));
+
+        (lo.x..=hi.x).contains(&point.x)
+            && (lo.y..=hi.y).contains(&point.y)
+            && (lo.depth()..=hi.depth()).contains(&point.depth())
+            && self.points().any(|p| p == point)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum LineKind {
+    // along a single axis
+    Axis,
+    // exactly 45°, or moving the same distance along each axis in 3D
+    Diagonal,
+    General,
+}
+
+// smallest and largest corner of the box containing every line
+fn bounding_box<'a>(lines: impl Iterator<Item = &'a Line>) -> Option<(Point, Point)> {
+    lines
+        .flat_map(|line| [line.0, line.1])
+        .fold(None, |bounds, p| match bounds {
+            None => Some((p, p)),
+            Some((lo, hi)) => Some((lo.lower(p), hi.upper(p))),
+        })
+}
+
+trait Plane {
+    fn increment(&mut self, point: Point);
+
+    // sparse planes may skip points that no line covers
+    fn for_each_point(&self, f: impl FnMut(Point, u16));
+
+    fn add_line(&mut self, line: &Line) {
+        line.points().for_each(|p| self.increment(p))
+    }
+
+    fn add_lines<'a>(&mut self, lines: impl Iterator<Item = &'a Line>) {
+        lines.for_each(|l| self.add_line(l))
+    }
+
+    fn get(&self, point: Point) -> u16;
+
+    fn count_if(&self, mut p: impl FnMut(Point, u16) -> bool) -> usize {
+        let mut result = 0;
+
+        self.for_each_point(|point, value| {
+            if p(point, value) {
+                result += 1;
+            }
+        });
+
+        result
+    }
+
+    // the k most overlapped points, ties broken by row then column
+    fn top(&self, k: usize) -> Vec<(Point, u16)> {
+        let mut points = Vec::new();
+
+        self.for_each_point(|point, value| {
+            if value > 0 {
+                points.push((point, value))
+            }
+        });
+
+        points.sort_by_key(|(p, v)| (Reverse(*v), p.depth(), p.y, p.x));
+        points.truncate(k);
+        points
     }
 }
 
-struct Plane(Vec<Vec<u16>>);
+// a grid covering only the bounding box, offset by its smallest corner. all
+// its points share the origin's z
+struct DensePlane {
+    origin: Point,
+    cells: Vec<Vec<u16>>,
+}
 
-impl Display for Plane {
+impl Display for DensePlane {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{}",
-            self.0
+            self.cells
                 .iter()
                 .map(|row| {
                     row.iter()
@@ -79,89 +287,1252 @@ impl Display for Plane {
     }
 }
 
-impl Plane {
-    fn new(width: usize, height: usize) -> Self {
-        Plane(vec![vec![0; width]; height])
+impl DensePlane {
+    fn new(min: Point, max: Point) -> Self {
+        let width = max.x.abs_diff(min.x) as usize + 1;
+        let height = max.y.abs_diff(min.y) as usize + 1;
+
+        DensePlane {
+            origin: min,
+            cells: vec![vec![0; width]; height],
+        }
     }
 
-    fn add_line(&mut self, line: &Line) {
-        let min_x = min(line.0.x, line.1.x) as usize;
-        let max_x = max(line.0.x, line.1.x) as usize;
-        let min_y = min(line.0.y, line.1.y) as usize;
-        let max_y = max(line.0.y, line.1.y) as usize;
-
-        if line.horizontal() || line.vertical() {
-            for y in min_y..max_y + 1 {
-                for x in min_x..max_x + 1 {
-                    self.0[y][x] += 1
-                }
-            }
-        } else {
-            let leftmost_point = if min_x == line.0.x as usize {
-                &line.0
-            } else {
-                &line.1
-            };
+    // row and column of the point, None if it's off the plane
+    fn index(&self, point: Point) -> Option<(usize, usize)> {
+        let offset = |v: i32, origin: i32| usize::try_from(v as i64 - origin as i64).ok();
 
-            let upwards = leftmost_point.y < max_y as u16;
+        if point.depth() != self.origin.depth() {
+            return None;
+        }
 
-            let mut x = leftmost_point.x as usize;
-            let mut y = leftmost_point.y as usize;
+        let (y, x) = (
+            offset(point.y, self.origin.y)?,
+            offset(point.x, self.origin.x)?,
+        );
 
-            while x <= max_x {
-                self.0[y][x] += 1;
-                x += 1;
+        (y < self.cells.len() && x < self.width()).then_some((y, x))
+    }
+}
 
-                if upwards {
-                    y += 1;
-                } else {
-                    y -= 1;
-                }
-            }
-        }
+impl Plane for DensePlane {
+    fn increment(&mut self, point: Point) {
+        let (y, x) = self.index(point).unwrap();
+        self.cells[y][x] += 1
     }
 
-    fn add_lines<'a>(&mut self, lines: impl Iterator<Item = &'a Line>) {
-        lines.for_each(|l| self.add_line(&l))
+    fn get(&self, point: Point) -> u16 {
+        self.index(point).map_or(0, |(y, x)| self.cells[y][x])
     }
 
     fn for_each_point(&self, mut f: impl FnMut(Point, u16)) {
-        self.0.iter().enumerate().for_each(|(y, row)| {
+        self.cells.iter().enumerate().for_each(|(y, row)| {
             row.iter().enumerate().for_each(|(x, value)| {
                 f(
                     Point {
-                        x: x as u16,
-                        y: y as u16,
+                        x: self.origin.x + x as i32,
+                        y: self.origin.y + y as i32,
+                        ..self.origin
                     },
                     *value,
                 )
             })
         })
     }
+}
 
-    fn count_if(&self, mut p: impl FnMut(Point, u16) -> bool) -> usize {
-        let mut result = 0;
+// a grid over a 3D bounding box, for lines that leave their plane
+struct VolumePlane {
+    origin: Point,
+    size: (usize, usize, usize),
+    cells: Vec<u16>,
+}
 
-        self.for_each_point(|point, value| {
-            if p(point, value) {
-                result += 1;
+impl VolumePlane {
+    fn new(min: Point, max: Point) -> Self {
+        let size = (
+            max.x.abs_diff(min.x) as usize + 1,
+            max.y.abs_diff(min.y) as usize + 1,
+            max.depth().abs_diff(min.depth()) as usize + 1,
+        );
+
+        VolumePlane {
+            origin: min,
+            size,
+            cells: vec![0; size.0 * size.1 * size.2],
+        }
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        let offset = |v: i32, origin: i32, size: usize| {
+            usize::try_from(v as i64 - origin as i64)
+                .ok()
+                .filter(|&o| o < size)
+        };
+
+        let x = offset(point.x, self.origin.x, self.size.0)?;
+        let y = offset(point.y, self.origin.y, self.size.1)?;
+        let z = offset(point.depth(), self.origin.depth(), self.size.2)?;
+
+        Some((z * self.size.1 + y) * self.size.0 + x)
+    }
+}
+
+impl Plane for VolumePlane {
+    fn increment(&mut self, point: Point) {
+        let i = self.index(point).unwrap();
+        self.cells[i] += 1
+    }
+
+    fn get(&self, point: Point) -> u16 {
+        self.index(point).map_or(0, |i| self.cells[i])
+    }
+
+    fn for_each_point(&self, mut f: impl FnMut(Point, u16)) {
+        let (width, height, _depth) = self.size;
+
+        self.cells.iter().enumerate().for_each(|(i, value)| {
+            f(
+                Point::new_3d(
+                    self.origin.x + (i % width) as i32,
+                    self.origin.y + (i / width % height) as i32,
+                    self.origin.depth() + (i / width / height) as i32,
+                ),
+                *value,
+            )
+        })
+    }
+}
+
+// only stores covered points, for coordinate ranges too big to allocate
+#[derive(Default)]
+struct SparsePlane(HashMap<Point, u16>);
+
+impl Plane for SparsePlane {
+    fn increment(&mut self, point: Point) {
+        *self.0.entry(point).or_default() += 1
+    }
+
+    fn get(&self, point: Point) -> u16 {
+        self.0.get(&point).copied().unwrap_or(0)
+    }
+
+    fn for_each_point(&self, mut f: impl FnMut(Point, u16)) {
+        self.0.iter().for_each(|(point, value)| f(*point, *value))
+    }
+}
+
+// bounding boxes with more cells than this use a SparsePlane
+const MAX_DENSE_CELLS: usize = 1 << 24;
+
+fn cells_between(lo: Point, hi: Point) -> usize {
+    [
+        hi.x.abs_diff(lo.x),
+        hi.y.abs_diff(lo.y),
+        hi.depth().abs_diff(lo.depth()),
+    ]
+    .iter()
+    .fold(1usize, |cells, &d| cells.saturating_mul(d as usize + 1))
+}
+
+fn count_overlaps<'a>(lines: impl Iterator<Item = &'a Line> + Clone) -> usize {
+    fn count<'a>(mut plane: impl Plane, lines: impl Iterator<Item = &'a Line>) -> usize {
+        plane.add_lines(lines);
+        plane.count_if(|_p, v| v > 1)
+    }
+
+    match bounding_box(lines.clone()) {
+        None => 0,
+        Some((lo, hi)) if cells_between(lo, hi) > MAX_DENSE_CELLS => {
+            count(SparsePlane::default(), lines)
+        }
+        Some((lo, hi)) if lo.depth() == hi.depth() => count(DensePlane::new(lo, hi), lines),
+        Some((lo, hi)) => count(VolumePlane::new(lo, hi), lines),
+    }
+}
+
+// the infinite lines axis-aligned and 45° segments lie on, as `a*x + b*y = c`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Family {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    AntiDiagonal,
+}
+
+impl Family {
+    const ALL: [Family; 4] = [
+        Family::Horizontal,
+        Family::Vertical,
+        Family::Diagonal,
+        Family::AntiDiagonal,
+    ];
+
+    fn coefficients(self) -> (i64, i64) {
+        match self {
+            Family::Horizontal => (0, 1),
+            Family::Vertical => (1, 0),
+            Family::Diagonal => (1, -1),
+            Family::AntiDiagonal => (1, 1),
+        }
+    }
+
+    // `c` of the family line through the point
+    fn constant(self, (x, y): (i64, i64)) -> i64 {
+        let (a, b) = self.coefficients();
+        a * x + b * y
+    }
+
+    // position along the family line, used for the intervals segments cover
+    fn position(self, (x, y): (i64, i64)) -> i64 {
+        match self {
+            Family::Vertical => y,
+            _ => x,
+        }
+    }
+}
+
+// a family line in the plane at some depth, identified by its family,
+// constant and z
+type Track = (Family, i64, i32);
+
+// sorted, disjoint, inclusive intervals of positions along a track
+type Runs = Vec<(i64, i64)>;
+
+impl Line {
+    // the track the line lies on and the interval it covers along it. None
+    // for general lines and lines that change depth. a single point counts
+    // as horizontal
+    fn track(&self) -> Option<(Track, (i64, i64))> {
+        if self.0.depth() != self.1.depth() {
+            return None;
+        }
+
+        let (x0, y0) = (self.0.x as i64, self.0.y as i64);
+        let (x1, y1) = (self.1.x as i64, self.1.y as i64);
+
+        let family = match self.kind() {
+            LineKind::Axis if self.horizontal() => Family::Horizontal,
+            LineKind::Axis => Family::Vertical,
+            LineKind::Diagonal if x1 - x0 == y1 - y0 => Family::Diagonal,
+            LineKind::Diagonal => Family::AntiDiagonal,
+            LineKind::General => return None,
+        };
+
+        let (t0, t1) = (family.position((x0, y0)), family.position((x1, y1)));
+
+        Some((
+            (family, family.constant((x0, y0)), self.0.depth()),
+            (min(t0, t1), max(t0, t1)),
+        ))
+    }
+}
+
+// lattice point where two tracks of different families at the same depth
+// cross
+fn crossing((f1, c1, z1): Track, (f2, c2, z2): Track) -> Option<(i64, i64)> {
+    if z1 != z2 {
+        return None;
+    }
+
+    let (a1, b1) = f1.coefficients();
+    let (a2, b2) = f2.coefficients();
+    let det = a1 * b2 - a2 * b1;
+    let x = c1 * b2 - c2 * b1;
+    let y = a1 * c2 - a2 * c1;
+
+    (det != 0 && x % det == 0 && y % det == 0).then(|| (x / det, y / det))
+}
+
+// the runs covered at least once and at least twice
+fn sweep(intervals: &[(i64, i64)]) -> (Runs, Runs) {
+    fn push(runs: &mut Runs, from: i64, to: i64) {
+        match runs.last_mut() {
+            Some(last) if last.1 + 1 == from => last.1 = to,
+            _ => runs.push((from, to)),
+        }
+    }
+
+    let mut events = intervals
+        .iter()
+        .flat_map(|&(from, to)| [(from, 1), (to + 1, -1)])
+        .collect::<Vec<(i64, i32)>>();
+    events.sort();
+
+    let mut depth = 0;
+    let mut once = Vec::new();
+    let mut twice = Vec::new();
+
+    for (i, &(position, delta)) in events.iter().enumerate() {
+        depth += delta;
+
+        let next = match events.get(i + 1) {
+            Some(&(next, _)) if next == position => continue,
+            Some(&(next, _)) => next,
+            None => break,
+        };
+
+        if depth >= 1 {
+            push(&mut once, position, next - 1);
+        }
+
+        if depth >= 2 {
+            push(&mut twice, position, next - 1);
+        }
+    }
+
+    (once, twice)
+}
+
+fn covers(runs: &[(i64, i64)], t: i64) -> bool {
+    let i = runs.partition_point(|run| run.1 < t);
+    runs.get(i).is_some_and(|run| run.0 <= t)
+}
+
+// counts points covered by two or more lines without rasterizing them. a
+// point is covered twice either by overlapping lines on one track, found
+// with a 1D sweep per track, or where covered tracks of different families
+// cross.
+// crossings are found by testing every pair of covered runs, so this is
+// quadratic in the number of runs but independent of the coordinates.
+// None if any line changes depth or isn't axis-aligned or 45°
+fn count_overlaps_sweep<'a>(lines: impl Iterator<Item = &'a Line>) -> Option<usize> {
+    let mut tracks: HashMap<Track, Vec<(i64, i64)>> = HashMap::new();
+
+    for line in lines {
+        let (track, interval) = line.track()?;
+        tracks.entry(track).or_default().push(interval);
+    }
+
+    let swept: HashMap<Track, (Runs, Runs)> = tracks
+        .iter()
+        .map(|(track, intervals)| (*track, sweep(intervals)))
+        .collect();
+
+    let on_track = swept
+        .values()
+        .flat_map(|(_once, twice)| twice)
+        .map(|(from, to)| (to - from + 1) as usize)
+        .sum::<usize>();
+
+    let runs = swept
+        .iter()
+        .flat_map(|(track, (once, _twice))| once.iter().map(move |run| (*track, *run)))
+        .collect::<Vec<(Track, (i64, i64))>>();
+
+    let mut crossings = HashSet::new();
+
+    for (i, &(t1, r1)) in runs.iter().enumerate() {
+        for &(t2, r2) in &runs[i + 1..] {
+            if let Some(point) = crossing(t1, t2) {
+                let (p1, p2) = (t1.0.position(point), t2.0.position(point));
+
+                if r1.0 <= p1 && p1 <= r1.1 && r2.0 <= p2 && p2 <= r2.1 {
+                    crossings.insert((point, t1.2));
+                }
             }
-        });
+        }
+    }
 
-        result
+    // how many tracks already counted the point as covered twice
+    let counted_on_tracks = |(point, z): ((i64, i64), i32)| {
+        Family::ALL
+            .iter()
+            .filter(|&&family| {
+                swept
+                    .get(&(family, family.constant(point), z))
+                    .is_some_and(|(_once, twice)| covers(twice, family.position(point)))
+            })
+            .count() as isize
+    };
+
+    // every point covered twice on more than one track is also a crossing,
+    // so crossings both add the points no track counted and remove the
+    // duplicates
+    let correction = crossings
+        .into_iter()
+        .map(|point| match counted_on_tracks(point) {
+            0 => 1,
+            k => 1 - k,
+        })
+        .sum::<isize>();
+
+    Some((on_track as isize + correction) as usize)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Engine {
+    Raster,
+    Sweep,
+}
+
+// the sweep engine falls back to rasterizing when it meets a line it can't
+// put on a track
+fn count_with<'a>(engine: Engine, lines: impl Iterator<Item = &'a Line> + Clone) -> usize {
+    match engine {
+        Engine::Raster => count_overlaps(lines),
+        Engine::Sweep => {
+            count_overlaps_sweep(lines.clone()).unwrap_or_else(|| count_overlaps(lines))
+        }
+    }
+}
+
+pub fn part1(lines: &[Line], engine: Engine) -> usize {
+    count_with(engine, lines.iter().filter(|l| l.kind() == LineKind::Axis))
+}
+
+pub fn part2(lines: &[Line], engine: Engine) -> usize {
+    count_with(
+        engine,
+        lines.iter().filter(|l| l.kind() != LineKind::General),
+    )
+}
+
+// every line, including ones that aren't axis-aligned or 45°
+pub fn part3(lines: &[Line], engine: Engine) -> usize {
+    count_with(engine, lines.iter())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    // greyscale, brighter with more overlaps
+    Pgm,
+    // colour, using `ramp`
+    Ppm,
+    Svg,
+}
+
+impl FromStr for ImageFormat {
+    type Err = PlaneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pgm" => Ok(ImageFormat::Pgm),
+            "ppm" => Ok(ImageFormat::Ppm),
+            "svg" => Ok(ImageFormat::Svg),
+            _ => Err(PlaneError(format!("unknown image format: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PlaneError(String);
+
+impl Display for PlaneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
-pub fn part1(lines: &[Line]) -> u16 {
-    let mut plane = Plane::new(1000, 1000);
+// colour for an overlap count: black where nothing is covered, then from
+// blue for a single line through yellow to red at the maximum
+fn ramp(value: u16, max: u16) -> (u8, u8, u8) {
+    const STOPS: [(f64, f64, f64); 5] = [
+        (49.0, 54.0, 149.0),
+        (116.0, 173.0, 209.0),
+        (254.0, 224.0, 144.0),
+        (244.0, 109.0, 67.0),
+        (165.0, 0.0, 38.0),
+    ];
+
+    if value == 0 {
+        return (0, 0, 0);
+    }
+
+    let t = if max > 1 {
+        (value - 1) as f64 / (max - 1) as f64 * (STOPS.len() - 1) as f64
+    } else {
+        0.0
+    };
+    let i = min(t as usize, STOPS.len() - 2);
+    let (from, to, f) = (STOPS[i], STOPS[i + 1], t - i as f64);
+    let mix = |a: f64, b: f64| (a + (b - a) * f).round() as u8;
 
-    plane.add_lines(lines.iter().filter(|l| l.horizontal() || l.vertical()));
-    plane.count_if(|_p, v| v > 1) as u16
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
 }
 
-pub fn part2(lines: &[Line]) -> u16 {
-    let mut plane = Plane::new(1000, 1000);
+impl DensePlane {
+    fn width(&self) -> usize {
+        self.cells.first().map_or(0, |row| row.len())
+    }
+
+    fn max(&self) -> u16 {
+        self.cells.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    fn to_pgm(&self) -> Vec<u8> {
+        let max = self.max().max(1);
+        let mut image = format!("P5\n{} {}\n255\n", self.width(), self.cells.len()).into_bytes();
+
+        image.extend(
+            self.cells
+                .iter()
+                .flatten()
+                .map(|&v| (v as u32 * 255 / max as u32) as u8),
+        );
+        image
+    }
 
+    fn to_ppm(&self) -> Vec<u8> {
+        let max = self.max();
+        let mut image = format!("P6\n{} {}\n255\n", self.width(), self.cells.len()).into_bytes();
+
+        for &v in self.cells.iter().flatten() {
+            let (r, g, b) = ramp(v, max);
+            image.extend([r, g, b]);
+        }
+        image
+    }
+
+    // one square per covered cell, optionally with the lines drawn on top
+    // through the cell centres
+    fn to_svg(&self, overlay: &[Line]) -> String {
+        let max = self.max();
+        let (width, height) = (self.width(), self.cells.len());
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" \
+             shape-rendering=\"crispEdges\">\n<rect width=\"{}\" height=\"{}\" fill=\"black\"/>\n",
+            width, height, width, height
+        );
+
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, &v) in row.iter().enumerate().filter(|(_x, v)| **v > 0) {
+                let (r, g, b) = ramp(v, max);
+                svg += &format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"#{:02x}{:02x}{:02x}\"/>\n",
+                    x, y, r, g, b
+                );
+            }
+        }
+
+        let centre = |p: Point| {
+            (
+                (p.x - self.origin.x) as f64 + 0.5,
+                (p.y - self.origin.y) as f64 + 0.5,
+            )
+        };
+
+        for line in overlay {
+            let ((x1, y1), (x2, y2)) = (centre(line.0), centre(line.1));
+            svg += &format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"white\" stroke-width=\"0.2\"/>\n",
+                x1, y1, x2, y2
+            );
+        }
+
+        svg + "</svg>\n"
+    }
+}
+
+// a dense plane over the bounding box of all the lines
+fn dense_plane(lines: &[Line]) -> Result<DensePlane, PlaneError> {
+    let (lo, hi) = bounding_box(lines.iter()).ok_or(PlaneError(String::from("no lines")))?;
+    let cells = cells_between(lo, hi);
+
+    if lo.depth() != hi.depth() {
+        return Err(PlaneError(String::from("lines don't all lie in one plane")));
+    }
+
+    if cells > MAX_DENSE_CELLS {
+        return Err(PlaneError(format!(
+            "bounding box of {} cells is too big to render",
+            cells
+        )));
+    }
+
+    let mut plane = DensePlane::new(lo, hi);
     plane.add_lines(lines.iter());
-    plane.count_if(|_p, v| v > 1) as u16
+    Ok(plane)
+}
+
+// renders every line over its bounding box. overlays only apply to SVG
+pub fn export(lines: &[Line], format: ImageFormat, overlay: bool) -> Result<Vec<u8>, PlaneError> {
+    let plane = dense_plane(lines)?;
+
+    Ok(match format {
+        ImageFormat::Pgm => plane.to_pgm(),
+        ImageFormat::Ppm => plane.to_ppm(),
+        ImageFormat::Svg => plane.to_svg(if overlay { lines } else { &[] }).into_bytes(),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    Four,
+    // diagonal neighbours too
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(&self) -> &'static [(i32, i32)] {
+        match self {
+            Connectivity::Four => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Connectivity::Eight => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        }
+    }
+
+    fn neighbours(&self, point: Point) -> impl Iterator<Item = Point> {
+        self.offsets().iter().filter_map(move |(dx, dy)| {
+            Some(Point {
+                x: point.x.checked_add(*dx)?,
+                y: point.y.checked_add(*dy)?,
+                ..point
+            })
+        })
+    }
+}
+
+// the overlap counts of a set of lines, indexed for queries
+pub struct VentMap<'a> {
+    lines: &'a [Line],
+    plane: DensePlane,
+    // prefix[y][x] sums the cells above and left of (x, y), exclusive
+    prefix: Vec<Vec<u64>>,
+}
+
+impl<'a> VentMap<'a> {
+    pub fn new(lines: &'a [Line]) -> Result<Self, PlaneError> {
+        let plane = dense_plane(lines)?;
+        let width = plane.width();
+        let mut prefix = vec![vec![0; width + 1]; plane.cells.len() + 1];
+
+        for (y, row) in plane.cells.iter().enumerate() {
+            for (x, &v) in row.iter().enumerate() {
+                prefix[y + 1][x + 1] =
+                    v as u64 + prefix[y][x + 1] + prefix[y + 1][x] - prefix[y][x];
+            }
+        }
+
+        Ok(VentMap {
+            lines,
+            plane,
+            prefix,
+        })
+    }
+
+    pub fn get(&self, point: Point) -> u16 {
+        self.plane.get(point)
+    }
+
+    pub fn hotspots(&self, k: usize) -> Vec<(Point, u16)> {
+        self.plane.top(k)
+    }
+
+    pub fn covering(&self, point: Point) -> Vec<&'a Line> {
+        self.lines.iter().filter(|l| l.covers(point)).collect()
+    }
+
+    // total overlaps inside the rectangle with corners a and b, inclusive
+    pub fn region_sum(&self, a: Point, b: Point) -> u64 {
+        let origin = self.plane.origin;
+        let (width, height) = (self.plane.width(), self.plane.cells.len());
+        let clamp =
+            |v: i32, origin: i32, size: usize| min(max(v as i64 - origin as i64, 0) as usize, size);
+
+        let (x0, x1) = (min(a.x, b.x), max(a.x, b.x));
+        let (y0, y1) = (min(a.y, b.y), max(a.y, b.y));
+        if x1 < origin.x || y1 < origin.y {
+            return 0;
+        }
+
+        let (x0, y0) = (clamp(x0, origin.x, width), clamp(y0, origin.y, height));
+        let (x1, y1) = (
+            clamp(x1, origin.x, width - 1) + 1,
+            clamp(y1, origin.y, height - 1) + 1,
+        );
+
+        self.prefix[y1][x1] + self.prefix[y0][x0] - self.prefix[y0][x1] - self.prefix[y1][x0]
+    }
+
+    // groups of connected points with at least `threshold` overlaps, biggest
+    // first
+    pub fn clusters(&self, threshold: u16, connectivity: Connectivity) -> Vec<Vec<Point>> {
+        let threshold = max(threshold, 1);
+        let mut seen = HashSet::new();
+        let mut clusters = Vec::new();
+
+        self.plane.for_each_point(|start, value| {
+            if value < threshold || !seen.insert(start) {
+                return;
+            }
+
+            let mut cluster = Vec::new();
+            let mut queue = VecDeque::from([start]);
+
+            while let Some(point) = queue.pop_front() {
+                cluster.push(point);
+
+                for next in connectivity.neighbours(point) {
+                    if self.plane.get(next) >= threshold && seen.insert(next) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            cluster.sort_by_key(|p| (p.y, p.x));
+            clusters.push(cluster);
+        });
+
+        clusters.sort_by_key(|c| Reverse(c.len()));
+        clusters
+    }
+
+    // cheapest route between two points that never enters a cell with
+    // `threshold` or more overlaps. each step costs one plus the overlaps of
+    // the cell it enters, so among safe routes the least crowded wins. the
+    // search may leave the plane by one cell to walk around its edges
+    pub fn route(
+        &self,
+        start: Point,
+        end: Point,
+        threshold: u16,
+        connectivity: Connectivity,
+    ) -> Option<Route> {
+        let (lo, hi) = self.bounds(&[start, end]);
+        let lo = Point {
+            x: lo.x.saturating_sub(1),
+            y: lo.y.saturating_sub(1),
+            ..lo
+        };
+        let hi = Point {
+            x: hi.x.saturating_add(1),
+            y: hi.y.saturating_add(1),
+            ..hi
+        };
+        let safe = |p: Point| {
+            (lo.x..=hi.x).contains(&p.x) && (lo.y..=hi.y).contains(&p.y) && self.get(p) < threshold
+        };
+
+        // never overestimates, since every step costs at least one
+        let estimate = |p: Point| {
+            let (dx, dy) = (p.x.abs_diff(end.x) as u64, p.y.abs_diff(end.y) as u64);
+            match connectivity {
+                Connectivity::Four => dx + dy,
+                Connectivity::Eight => max(dx, dy),
+            }
+        };
+
+        if !safe(start) || !safe(end) {
+            return None;
+        }
+
+        let mut costs = HashMap::from([(start, 0)]);
+        let mut previous = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((estimate(start), 0, start))]);
+
+        while let Some(Reverse((_, cost, point))) = queue.pop() {
+            if point == end {
+                let mut points = vec![end];
+                while let Some(&p) = previous.get(points.last().unwrap()) {
+                    points.push(p);
+                }
+                points.reverse();
+
+                return Some(Route { points, cost });
+            }
+
+            if cost > costs[&point] {
+                continue;
+            }
+
+            for next in connectivity.neighbours(point).filter(|&p| safe(p)) {
+                let next_cost = cost + 1 + self.get(next) as u64;
+
+                if costs.get(&next).is_none_or(|&c| next_cost < c) {
+                    costs.insert(next, next_cost);
+                    previous.insert(next, point);
+                    queue.push(Reverse((next_cost + estimate(next), next_cost, next)));
+                }
+            }
+        }
+
+        None
+    }
+
+    // the plane, grown to take in `points`
+    fn bounds(&self, points: &[Point]) -> (Point, Point) {
+        let origin = self.plane.origin;
+        let corner = Point {
+            x: origin.x + self.plane.width() as i32 - 1,
+            y: origin.y + self.plane.cells.len() as i32 - 1,
+            ..origin
+        };
+
+        points
+            .iter()
+            .fold((origin, corner), |(lo, hi), &p| (lo.lower(p), hi.upper(p)))
+    }
+
+    // the plane as in its Display, with the route drawn over it as `*`
+    pub fn render(&self, route: &Route) -> String {
+        let (lo, hi) = self.bounds(&route.points);
+        let on_route: HashSet<&Point> = route.points.iter().collect();
+
+        (lo.y..=hi.y)
+            .map(|y| {
+                (lo.x..=hi.x)
+                    .map(|x| match (Point { x, y, ..lo }) {
+                        p if on_route.contains(&p) => '*',
+                        p => match self.get(p) {
+                            0 => '.',
+                            v => char::from_digit(min(v, 9) as u32, 10).unwrap(),
+                        },
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug)]
+pub struct Route {
+    points: Vec<Point>,
+    cost: u64,
+}
+
+impl Route {
+    pub fn steps(&self) -> usize {
+        self.points.len() - 1
+    }
+
+    pub fn cost(&self) -> u64 {
+        self.cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    const EXAMPLE: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
+    fn lines(s: &str) -> Vec<Line> {
+        s.lines().map(|l| Line::from_str(l).unwrap()).collect()
+    }
+
+    #[test]
+    fn example() {
+        for engine in [Engine::Raster, Engine::Sweep] {
+            assert_eq!(part1(&lines(EXAMPLE), engine), 5);
+            assert_eq!(part2(&lines(EXAMPLE), engine), 12);
+        }
+    }
+
+    #[test]
+    fn dense_plane_display() {
+        let lines = lines(EXAMPLE);
+        let (lo, hi) = bounding_box(lines.iter()).unwrap();
+        let mut plane = DensePlane::new(lo, hi);
+        plane.add_lines(lines.iter());
+
+        assert_eq!(plane.to_string().lines().next(), Some("1.1....11."));
+    }
+
+    #[test]
+    fn sparse_plane_matches_dense() {
+        let lines = lines(EXAMPLE);
+        let (lo, hi) = bounding_box(lines.iter()).unwrap();
+        let mut dense = DensePlane::new(lo, hi);
+        let mut sparse = SparsePlane::default();
+        dense.add_lines(lines.iter());
+        sparse.add_lines(lines.iter());
+
+        assert_eq!(
+            sparse.count_if(|_p, v| v > 1),
+            dense.count_if(|_p, v| v > 1)
+        );
+        sparse.for_each_point(|p, v| assert_eq!(dense.cells[p.y as usize][p.x as usize], v));
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let lines =
+            lines("60000,60000 -> 60010,60000\n60005,59990 -> 60005,65535\n0,0 -> 65535,65535");
+
+        for engine in [Engine::Raster, Engine::Sweep] {
+            assert_eq!(part1(&lines, engine), 1);
+            assert_eq!(part2(&lines, engine), 3);
+        }
+    }
+
+    #[test]
+    fn sweep() {
+        assert_eq!(
+            super::sweep(&[(0, 4), (2, 6), (3, 3), (8, 9), (10, 10)]),
+            (vec![(0, 6), (8, 10)], vec![(2, 4)])
+        );
+    }
+
+    #[test]
+    fn crossings() {
+        let diagonal = (Family::Diagonal, 0, 0);
+
+        assert_eq!(
+            crossing((Family::Horizontal, 3, 0), (Family::Vertical, 5, 0)),
+            Some((5, 3))
+        );
+        assert_eq!(
+            crossing(diagonal, (Family::AntiDiagonal, 4, 0)),
+            Some((2, 2))
+        );
+        assert_eq!(crossing(diagonal, (Family::AntiDiagonal, 3, 0)), None);
+        assert_eq!(crossing(diagonal, (Family::Diagonal, 2, 0)), None);
+        assert_eq!(crossing(diagonal, (Family::AntiDiagonal, 4, 1)), None);
+    }
+
+    #[test]
+    fn sweep_matches_raster() {
+        let mut rng = Rng(1);
+        let mut next = |n: u64| rng.below(n) as i32;
+
+        for _ in 0..20 {
+            let lines = (0..100)
+                .map(|_| {
+                    let (x, y, len) = (next(40) - 20, next(40) - 20, next(20));
+                    let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1)][next(4) as usize];
+
+                    Line(Point::new(x, y), Point::new(x + dx * len, y + dy * len))
+                })
+                .collect::<Vec<Line>>();
+
+            assert_eq!(part2(&lines, Engine::Sweep), part2(&lines, Engine::Raster));
+        }
+    }
+
+    #[test]
+    fn sweep_at_depth() {
+        let lines = lines("0,0,5 -> 2,0,5\n1,0,5 -> 1,3,5\n1,0,4 -> 1,3,4\n0,0 -> 2,2");
+
+        assert_eq!(count_overlaps_sweep(lines.iter()), Some(1));
+        assert_eq!(part2(&lines, Engine::Raster), 1);
+    }
+
+    #[test]
+    fn sweep_falls_back_to_raster() {
+        let lines = lines("0,0 -> 4,2\n0,2 -> 4,0");
+
+        assert_eq!(part3(&lines, Engine::Sweep), part3(&lines, Engine::Raster));
+    }
+
+    fn points(line: &str) -> Vec<(i32, i32)> {
+        Line::from_str(line)
+            .unwrap()
+            .points()
+            .map(|p| (p.x, p.y))
+            .collect()
+    }
+
+    #[test]
+    fn line_kinds() {
+        let kinds = lines("1,1 -> 1,3\n9,7 -> 7,7\n1,1 -> 3,3\n9,7 -> 7,9\n0,0 -> 4,2\n5,5 -> 5,5")
+            .iter()
+            .map(Line::kind)
+            .collect::<Vec<LineKind>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                LineKind::Axis,
+                LineKind::Axis,
+                LineKind::Diagonal,
+                LineKind::Diagonal,
+                LineKind::General,
+                LineKind::Axis
+            ]
+        );
+    }
+
+    #[test]
+    fn rasterize() {
+        assert_eq!(points("9,7 -> 7,7"), vec![(9, 7), (8, 7), (7, 7)]);
+        assert_eq!(points("0,2 -> 2,0"), vec![(0, 2), (1, 1), (2, 0)]);
+        assert_eq!(points("5,5 -> 5,5"), vec![(5, 5)]);
+        assert_eq!(
+            points("0,0 -> 4,2"),
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(points("2,0 -> 0,5").len(), 6);
+    }
+
+    #[test]
+    fn general_lines() {
+        let lines = lines("0,0 -> 4,2\n0,2 -> 4,0\n0,1 -> 4,1");
+
+        assert_eq!(part2(&lines, Engine::Raster), 0);
+        assert_eq!(part3(&lines, Engine::Raster), 2);
+    }
+
+    #[test]
+    fn colour_ramp() {
+        assert_eq!(ramp(0, 5), (0, 0, 0));
+        assert_eq!(ramp(1, 5), (49, 54, 149));
+        assert_eq!(ramp(5, 5), (165, 0, 38));
+        assert_eq!(ramp(1, 1), (49, 54, 149));
+        assert_eq!(ramp(2, 3), (254, 224, 144));
+    }
+
+    #[test]
+    fn export_images() {
+        let lines = lines("0,0 -> 2,0\n1,0 -> 1,1");
+
+        assert_eq!(
+            export(&lines, ImageFormat::Pgm, false).unwrap(),
+            [b"P5\n3 2\n255\n".as_slice(), &[127, 255, 127, 0, 127, 0]].concat()
+        );
+
+        let ppm = export(&lines, ImageFormat::Ppm, false).unwrap();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 3 * 6);
+        assert_eq!(&ppm[14..17], &[165, 0, 38]);
+
+        let svg = String::from_utf8(export(&lines, ImageFormat::Svg, true).unwrap()).unwrap();
+        assert!(svg.contains("viewBox=\"0 0 3 2\""));
+        assert_eq!(svg.matches("<rect").count(), 1 + 4);
+        assert!(svg.contains("<line x1=\"1.5\" y1=\"0.5\" x2=\"1.5\" y2=\"1.5\""));
+
+        let svg = String::from_utf8(export(&lines, ImageFormat::Svg, false).unwrap()).unwrap();
+        assert!(!svg.contains("<line"));
+    }
+
+    #[test]
+    fn export_errors() {
+        assert!(export(&[], ImageFormat::Svg, false).is_err());
+        assert!(export(&lines("0,0 -> 65535,65535"), ImageFormat::Pgm, false).is_err());
+        assert!(ImageFormat::from_str("png").is_err());
+    }
+
+    #[test]
+    fn hotspots() {
+        let lines = lines(EXAMPLE);
+        let map = VentMap::new(&lines).unwrap();
+
+        assert_eq!(
+            map.hotspots(3),
+            [
+                (Point::new(4, 4), 3),
+                (Point::new(6, 4), 3),
+                (Point::new(7, 1), 2)
+            ]
+        );
+        assert_eq!(map.hotspots(100).len(), 39);
+        assert_eq!(map.get(Point::new(4, 4)), 3);
+        assert_eq!(map.get(Point::new(100, 100)), 0);
+        assert_eq!(SparsePlane::default().top(1), []);
+    }
+
+    #[test]
+    fn covering() {
+        let lines = lines(EXAMPLE);
+        let map = VentMap::new(&lines).unwrap();
+        let covering = |x, y| {
+            map.covering(Point::new(x, y))
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(covering(4, 4), ["8,0 -> 0,8", "9,4 -> 3,4", "0,0 -> 8,8"]);
+        assert_eq!(covering(1, 9), ["0,9 -> 5,9", "0,9 -> 2,9"]);
+        assert!(covering(9, 9).is_empty());
+        assert_eq!(covering(2, 1), ["2,2 -> 2,1"]);
+    }
+
+    #[test]
+    fn region_sum() {
+        let lines = lines(EXAMPLE);
+        let map = VentMap::new(&lines).unwrap();
+        let total: u64 = lines.iter().map(|l| l.points().count() as u64).sum();
+
+        assert_eq!(map.region_sum(Point::new(0, 0), Point::new(9, 9)), total);
+        assert_eq!(
+            map.region_sum(Point::new(0, 0), Point::new(500, 500)),
+            total
+        );
+        assert_eq!(map.region_sum(Point::new(4, 4), Point::new(4, 4)), 3);
+        assert_eq!(map.region_sum(Point::new(6, 4), Point::new(4, 4)), 7);
+        assert_eq!(map.region_sum(Point::new(0, 9), Point::new(9, 9)), 9);
+
+        let lines = self::lines("5,5 -> 7,5");
+        let map = VentMap::new(&lines).unwrap();
+        assert_eq!(map.region_sum(Point::new(0, 0), Point::new(4, 9)), 0);
+        assert_eq!(map.region_sum(Point::new(0, 0), Point::new(5, 9)), 1);
+        assert_eq!(map.region_sum(Point::new(8, 0), Point::new(9, 9)), 0);
+    }
+
+    #[test]
+    fn clusters() {
+        let lines = lines(EXAMPLE);
+        let map = VentMap::new(&lines).unwrap();
+        let sizes = |connectivity| {
+            map.clusters(2, connectivity)
+                .iter()
+                .map(|c| c.len())
+                .collect::<Vec<usize>>()
+        };
+
+        assert_eq!(sizes(Connectivity::Four), [3, 3, 2, 1, 1, 1, 1]);
+        assert_eq!(sizes(Connectivity::Eight), [7, 3, 1, 1]);
+        assert_eq!(
+            map.clusters(3, Connectivity::Four),
+            [[Point::new(4, 4)], [Point::new(6, 4)]]
+        );
+        assert_eq!(map.clusters(1, Connectivity::Eight).len(), 1);
+    }
+
+    #[test]
+    fn route() {
+        let lines = lines(EXAMPLE);
+        let map = VentMap::new(&lines).unwrap();
+        let (start, end) = (Point::new(9, 0), Point::new(9, 9));
+        let route = |threshold, connectivity| {
+            map.route(start, end, threshold, connectivity)
+                .map(|r| (r.steps(), r.cost()))
+        };
+
+        // around 9,4 through the margin, or straight over it at a price
+        assert_eq!(route(1, Connectivity::Four), Some((11, 11)));
+        assert_eq!(route(1, Connectivity::Eight), Some((9, 9)));
+        assert_eq!(route(2, Connectivity::Four), Some((9, 10)));
+        assert_eq!(route(0, Connectivity::Four), None);
+        assert!(map
+            .route(Point::new(4, 4), end, 3, Connectivity::Four)
+            .is_none());
+
+        // a wall is only passable around its far end
+        let lines = self::lines("5,0 -> 5,9\n5,0 -> 5,9");
+        let map = VentMap::new(&lines).unwrap();
+        let route = map
+            .route(Point::new(0, 5), Point::new(9, 5), 2, Connectivity::Four)
+            .unwrap();
+        assert_eq!((route.steps(), route.cost()), (19, 19));
+        assert!(route.points.contains(&Point::new(5, 10)));
+    }
+
+    #[test]
+    fn render_route() {
+        let lines = lines("1,0 -> 1,1\n1,0 -> 1,1");
+        let map = VentMap::new(&lines).unwrap();
+        let route = map
+            .route(Point::new(0, 1), Point::new(2, 1), 2, Connectivity::Four)
+            .unwrap();
+
+        assert_eq!(route.steps(), 4);
+        assert_eq!(map.render(&route), ".2.\n*2*\n***");
+    }
+
+    #[test]
+    fn query_far_outside() {
+        let lines = lines("-10,-10 -> -5,-5");
+        let map = VentMap::new(&lines).unwrap();
+
+        assert_eq!(map.get(Point::new(i32::MAX, -7)), 0);
+        assert_eq!(map.get(Point::new(i32::MIN, i32::MAX)), 0);
+        assert_eq!(map.get(Point::new(-7, -7)), 1);
+    }
+
+    #[test]
+    fn parse_points() {
+        assert_eq!(Point::from_str("-3, 4"), Ok(Point::new(-3, 4)));
+        assert_eq!(Point::from_str("1,2,-3"), Ok(Point::new_3d(1, 2, -3)));
+        assert!(Point::from_str("1").is_err());
+        assert!(Point::from_str("1,2,3,4").is_err());
+        assert!(Point::from_str("a,b").is_err());
+        assert_eq!(
+            Line::from_str("-1,2 -> 3,4,5").unwrap().to_string(),
+            "-1,2 -> 3,4,5"
+        );
+        assert_eq!(
+            Line::from_str("0,0,0 -> 1,1,1").unwrap().to_string(),
+            "0,0,0 -> 1,1,1"
+        );
+        assert_eq!(Point::new(1, 2), Point::new_3d(1, 2, 0));
+    }
+
+    #[test]
+    fn signed_coordinates() {
+        let shift = |p: Point| Point::new(p.x - 500, p.y - 500);
+        let lines = lines(EXAMPLE)
+            .iter()
+            .map(|l| Line(shift(l.0), shift(l.1)))
+            .collect::<Vec<Line>>();
+
+        for engine in [Engine::Raster, Engine::Sweep] {
+            assert_eq!(part1(&lines, engine), 5);
+            assert_eq!(part2(&lines, engine), 12);
+        }
+    }
+
+    #[test]
+    fn lines_3d() {
+        let kind = |line| Line::from_str(line).unwrap().kind();
+
+        assert_eq!(kind("0,0,0 -> 0,0,5"), LineKind::Axis);
+        assert_eq!(kind("1,1,1 -> 3,-1,3"), LineKind::Diagonal);
+        assert_eq!(kind("0,0,0 -> 2,2,0"), LineKind::Diagonal);
+        assert_eq!(kind("0,0,0 -> 2,1,1"), LineKind::General);
+
+        let points = |line| {
+            Line::from_str(line)
+                .unwrap()
+                .points()
+                .map(|p| (p.x, p.y, p.depth()))
+                .collect::<Vec<(i32, i32, i32)>>()
+        };
+
+        assert_eq!(
+            points("0,0,0 -> 2,2,-2"),
+            [(0, 0, 0), (1, 1, -1), (2, 2, -2)]
+        );
+        assert_eq!(
+            points("0,0,0 -> 4,2,1"),
+            [(0, 0, 0), (1, 1, 0), (2, 1, 1), (3, 2, 1), (4, 2, 1)]
+        );
+    }
+
+    #[test]
+    fn volume_plane() {
+        let lines = lines("0,0,0 -> 0,0,4\n0,0,2 -> 4,0,2\n0,0,0 -> 4,4,4\n0,0,1 -> 0,0,3");
+
+        assert_eq!(part1(&lines, Engine::Raster), 3);
+        assert_eq!(part2(&lines, Engine::Raster), 4);
+        assert_eq!(count_overlaps_sweep(lines.iter()), None);
+
+        // parallel lines at different depths don't overlap
+        let layers = self::lines("0,0,0 -> 2,0,0\n0,0,1 -> 2,0,1");
+        assert_eq!(part1(&layers, Engine::Raster), 0);
+        assert!(VentMap::new(&layers).is_err());
+
+        let (lo, hi) = bounding_box(lines.iter()).unwrap();
+        let mut volume = VolumePlane::new(lo, hi);
+        let mut sparse = SparsePlane::default();
+        volume.add_lines(lines.iter());
+        sparse.add_lines(lines.iter());
+
+        assert_eq!(
+            volume.count_if(|_p, v| v > 0),
+            sparse.count_if(|_p, v| v > 0)
+        );
+        sparse.for_each_point(|p, v| assert_eq!(volume.get(p), v));
+        volume.for_each_point(|p, v| assert_eq!(sparse.get(p), v));
+    }
 }
@@ -1,67 +1,965 @@
-use std::num::ParseIntError;
+use std::fmt::{Display, Formatter};
+use std::ops::Sub;
 use std::str::FromStr;
 
-pub struct School([u64; 9]);
+use crate::args::{Options, ParseArgsError};
 
-impl School {
-    fn step(&mut self) {
-        for i in 0..self.0.len() {
-            if i > 0 {
-                self.0.swap(i - 1, i)
+// how a kind of fish breeds and dies
+#[derive(Debug, Clone, PartialEq)]
+pub struct Species {
+    // days between an adult's spawns
+    cycle: usize,
+    // extra days before a newborn's first cycle
+    maturity: usize,
+    // the fraction of every timer group that dies each day, rounded down
+    mortality: (u64, u64),
+}
+
+// lanternfish
+impl Default for Species {
+    fn default() -> Self {
+        Species {
+            cycle: 7,
+            maturity: 2,
+            mortality: (0, 1),
+        }
+    }
+}
+
+impl Species {
+    // --cycle N (default 7)
+    // --maturity N (default 2)
+    // --mortality DEATHS/FISH, e.g. 1/100 (default none)
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Species, ParseArgsError> {
+        let mut species = Species::default();
+        let mut options = Options::new(args);
+
+        while let Some(option) = options.next_option() {
+            match option.as_str() {
+                "--cycle" => species.cycle = options.parse()?,
+                "--maturity" => species.maturity = options.parse()?,
+                "--mortality" => species.mortality = options.pair('/')?,
+                _ => return Err(options.unknown()),
             }
         }
 
-        self.0[6] += self.0[8];
+        if species.cycle == 0 {
+            Err(ParseArgsError(String::from(
+                "the cycle must be at least a day",
+            )))
+        } else if species.mortality.1 == 0 || species.mortality.0 > species.mortality.1 {
+            Err(ParseArgsError(format!(
+                "mortality must be a fraction: {}/{}",
+                species.mortality.0, species.mortality.1
+            )))
+        } else {
+            Ok(species)
+        }
     }
 
-    fn n_steps(&mut self, n: u32) {
-        (0..n + 1).for_each(|_i| self.step())
+    // newborns start with the largest timer
+    fn timers(&self) -> usize {
+        self.cycle + self.maturity
     }
 
-    fn population(&self) -> u64 {
-        self.0.into_iter().fold(0, |a, b| a + b as u64)
+    // one day as a linear map on the fish counts. mortality isn't linear once
+    // deaths are rounded, so there's no matrix for species that die
+    fn transition(&self) -> Option<Matrix> {
+        if self.mortality.0 > 0 {
+            return None;
+        }
+
+        let n = self.timers();
+        let mut m = vec![vec![0; n]; n];
+
+        for (i, row) in m.iter_mut().enumerate().take(n - 1) {
+            row[i + 1] = 1;
+        }
+        m[n - 1][0] += 1;
+        m[self.cycle - 1][0] += 1;
+
+        Some(m)
     }
+
+    // the factor the population grows by each day in the long run, the
+    // dominant eigenvalue of the transition. without deaths it's the root
+    // above 1 of λ^n = λ^(n - cycle) + 1, n being the number of timers;
+    // mortality scales it by the surviving fraction
+    fn growth_ratio(&self) -> f64 {
+        let n = self.timers() as i32;
+        let f = |l: f64| l.powi(n) - l.powi(n - self.cycle as i32) - 1.0;
+        let (mut lo, mut hi) = (1.0, 2.0);
+
+        for _ in 0..100 {
+            let mid = (lo + hi) / 2.0;
+            if f(mid) < 0.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        lo * (1.0 - self.mortality.0 as f64 / self.mortality.1 as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
 }
 
-impl FromStr for School {
-    type Err = ParseIntError;
+impl FromStr for Format {
+    type Err = ParseArgsError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut fish_by_age = [0u64; 9];
+        match s {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(ParseArgsError(format!("unknown format: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseSchoolError(String);
+
+impl Display for ParseSchoolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// everything the school commands can fail with
+#[derive(Debug)]
+pub enum SchoolError {
+    Parse(ParseSchoolError),
+    Overflow(OverflowError),
+    ZeroModulus,
+    // there's no projection matrix for species that die
+    Mortality,
+}
+
+impl Display for SchoolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchoolError::Parse(e) => write!(f, "{}", e),
+            SchoolError::Overflow(e) => write!(f, "{}", e),
+            SchoolError::ZeroModulus => f.write_str("the modulus must be at least 1"),
+            SchoolError::Mortality => f.write_str("can't project a species with a mortality"),
+        }
+    }
+}
+
+impl From<ParseSchoolError> for SchoolError {
+    fn from(e: ParseSchoolError) -> Self {
+        SchoolError::Parse(e)
+    }
+}
+
+impl From<OverflowError> for SchoolError {
+    fn from(e: OverflowError) -> Self {
+        SchoolError::Overflow(e)
+    }
+}
+
+// the number of fish with each timer value
+pub struct School<C = u64> {
+    species: Species,
+    fish: Vec<C>,
+}
+
+type Matrix<T = u64> = Vec<Vec<T>>;
+
+// matrix arithmetic is either modular or saturating; `add_mul(sum, a, b)` is
+// `sum + a * b` in whichever it is
+fn multiply<T: Copy + Default>(
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    add_mul: impl Fn(T, T, T) -> T,
+) -> Matrix<T> {
+    let n = a.len();
+    let mut result = vec![vec![T::default(); n]; n];
+
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = (0..n).fold(T::default(), |sum, k| add_mul(sum, a[i][k], b[k][j]));
+        }
+    }
+
+    result
+}
+
+// m^n by repeated squaring
+fn power<T: Copy + Default>(
+    m: &Matrix<T>,
+    mut n: u64,
+    one: T,
+    add_mul: impl Fn(T, T, T) -> T + Copy,
+) -> Matrix<T> {
+    let size = m.len();
+    let mut result = (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| if i == j { one } else { T::default() })
+                .collect()
+        })
+        .collect();
+    let mut square = m.clone();
+
+    while n > 0 {
+        if n & 1 == 1 {
+            result = multiply(&result, &square, add_mul);
+        }
+        square = multiply(&square, &square, add_mul);
+        n >>= 1;
+    }
+
+    result
+}
+
+// m^n applied to the fish counts, summed
+fn project<T: Copy + Default>(
+    m: &Matrix<T>,
+    days: u64,
+    fish: impl Iterator<Item = T> + Clone,
+    one: T,
+    add_mul: impl Fn(T, T, T) -> T + Copy,
+) -> T {
+    power(m, days, one, add_mul)
+        .iter()
+        .fold(T::default(), |sum, row| {
+            row.iter()
+                .zip(fish.clone())
+                .fold(sum, |sum, (&a, fish)| add_mul(sum, a, fish))
+        })
+}
+
+// fish counts. u64 is enough for the puzzle; u128 keeps counts exact for
+// about twice as many days
+pub trait Count: Copy + Default + Display + Sub<Output = Self> {
+    const ONE: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    // deaths among `self` fish, rounded down
+    fn deaths(self, mortality: (u64, u64)) -> Self;
+
+    fn as_f64(self) -> f64;
+}
+
+impl Count for u64 {
+    const ONE: Self = 1;
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        self.checked_add(other)
+    }
+
+    fn deaths(self, (deaths, per): (u64, u64)) -> Self {
+        (self as u128 * deaths as u128 / per as u128) as u64
+    }
+
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Count for u128 {
+    const ONE: Self = 1;
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        self.checked_add(other)
+    }
+
+    // split so the product can't overflow
+    fn deaths(self, (deaths, per): (u64, u64)) -> Self {
+        let (deaths, per) = (deaths as u128, per as u128);
+        self / per * deaths + self % per * deaths / per
+    }
+
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+#[derive(Debug)]
+pub struct OverflowError {
+    day: u32,
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fish counts overflow on day {}", self.day)
+    }
+}
+
+impl<C: Count> School<C> {
+    pub fn parse(s: &str, species: &Species) -> Result<School<C>, ParseSchoolError> {
+        let mut fish = vec![C::default(); species.timers()];
+
+        for timer in s.split(",") {
+            let timer = timer.trim();
+            let count = usize::from_str(timer)
+                .ok()
+                .and_then(|t| fish.get_mut(t))
+                .ok_or_else(|| ParseSchoolError(format!("invalid timer: {}", timer)))?;
+            *count = count
+                .checked_add(C::ONE)
+                .ok_or_else(|| ParseSchoolError(String::from("too many fish")))?;
+        }
+
+        Ok(School {
+            species: species.clone(),
+            fish,
+        })
+    }
+
+    // some fish die, the rest count down. fish at 0 reset to the end of the
+    // cycle and spawn a newborn. None if a count or the population no longer
+    // fits, leaving the school part way through the day
+    fn step(&mut self) -> Option<()> {
+        for fish in self.fish.iter_mut() {
+            *fish = *fish - fish.deaths(self.species.mortality);
+        }
+
+        self.fish.rotate_left(1);
+
+        let (reset, newborn) = (self.species.cycle - 1, self.species.timers() - 1);
+        self.fish[reset] = self.fish[reset].checked_add(self.fish[newborn])?;
+        self.population().map(|_| ())
+    }
+
+    fn n_steps(&mut self, n: u32) -> Result<(), OverflowError> {
+        (1..=n).try_for_each(|day| self.step().ok_or(OverflowError { day }))
+    }
+
+    // the histogram of every day from today to `days` from now
+    fn record(&mut self, days: u32) -> Result<Series<C>, OverflowError> {
+        let mut histograms = vec![self.fish.clone()];
+
+        for day in 1..=days {
+            self.step().ok_or(OverflowError { day })?;
+            histograms.push(self.fish.clone());
+        }
+
+        Ok(Series {
+            species: self.species.clone(),
+            histograms,
+        })
+    }
+
+    pub fn histogram(&self) -> &[C] {
+        &self.fish
+    }
+
+    fn population(&self) -> Option<C> {
+        sum(&self.fish)
+    }
+}
+
+fn sum<C: Count>(fish: &[C]) -> Option<C> {
+    fish.iter()
+        .try_fold(C::default(), |sum, &fish| sum.checked_add(fish))
+}
+
+// the longest simulation `first_day_reaching` runs for species that die
+const MAX_SIMULATED_DAYS: u64 = 1_000_000;
+
+impl School<u64> {
+    // the population after `days`, modulo `modulus`, in O(log days) without
+    // simulating every day. the exact count overflows u64 around day 440.
+    // None for species with a mortality and for a zero modulus
+    fn population_after(&self, days: u64, modulus: u64) -> Option<u64> {
+        if modulus == 0 {
+            return None;
+        }
+
+        let add_mul = |sum: u64, a: u64, b: u64| {
+            ((sum as u128 + a as u128 * b as u128) % modulus as u128) as u64
+        };
+
+        Some(project(
+            &self.species.transition()?,
+            days,
+            self.fish.iter().copied(),
+            1 % modulus,
+            add_mul,
+        ))
+    }
+
+    // the first day the population is at least `target`, or None if it never
+    // gets there or dies out. without deaths the population never shrinks, so this
+    // doubles the day until the projection passes the target and then
+    // bisects. projections saturate rather than overflow, which can't change
+    // the answer since no target is above u128::MAX. species that die are
+    // simulated day by day instead, for up to MAX_SIMULATED_DAYS
+    fn first_day_reaching(&self, target: u128) -> Option<u64> {
+        let mut school = School::<u128> {
+            species: self.species.clone(),
+            fish: self.fish.iter().map(|&f| f as u128).collect(),
+        };
+
+        let Some(m) = self.species.transition() else {
+            for day in 0..=MAX_SIMULATED_DAYS {
+                match school.population()? {
+                    population if population >= target => return Some(day),
+                    0 => return None,
+                    _ => (),
+                }
+
+                // overflowing u128 passes any target
+                if school.step().is_none() {
+                    return Some(day + 1);
+                }
+            }
+
+            return None;
+        };
+
+        let m = m
+            .iter()
+            .map(|row| row.iter().map(|&a| a as u128).collect())
+            .collect::<Matrix<u128>>();
+        let add_mul = |sum: u128, a: u128, b: u128| sum.saturating_add(a.saturating_mul(b));
+        let reaches = |day| project(&m, day, school.fish.iter().copied(), 1, add_mul) >= target;
+
+        if reaches(0) {
+            return Some(0);
+        } else if school.population()? == 0 {
+            return None;
+        }
+
+        // the population grows at least every `timers` days, so the
+        // saturated projection passes any target long before this overflows
+        let (mut lo, mut hi) = (0, 1);
+        while !reaches(hi) {
+            lo = hi;
+            hi *= 2;
+        }
+
+        // reaches(lo) is false and reaches(hi) true
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if reaches(mid) {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+
+        Some(hi)
+    }
+}
+
+// a school's histogram on each day, starting from day 0
+pub struct Series<C> {
+    species: Species,
+    histograms: Vec<Vec<C>>,
+}
+
+impl<C: Count> Series<C> {
+    // every day's population fit when it was recorded
+    fn populations(&self) -> impl Iterator<Item = C> + '_ {
+        self.histograms.iter().map(|h| sum(h).unwrap())
+    }
+
+    pub fn export(&self, format: Format) -> String {
+        let days = self.populations().zip(&self.histograms).enumerate();
+        let join = |h: &[C]| {
+            h.iter()
+                .map(|fish| fish.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
+        match format {
+            Format::Csv => {
+                let timers = (0..self.species.timers())
+                    .map(|t| format!(",timer_{}", t))
+                    .collect::<String>();
 
-        s.split(",")
-            .map(|n| u64::from_str(n).unwrap())
-            .for_each(|fish| fish_by_age[fish as usize + 1] += 1);
+                days.fold(
+                    format!("day,population{}\n", timers),
+                    |csv, (day, (p, h))| csv + &format!("{},{},{}\n", day, p, join(h)),
+                )
+            }
+            Format::Json => {
+                let days = days
+                    .map(|(day, (p, h))| {
+                        format!(
+                            "  {{\"day\": {}, \"population\": {}, \"timers\": [{}]}}",
+                            day,
+                            p,
+                            join(h)
+                        )
+                    })
+                    .collect::<Vec<String>>();
+
+                format!("[\n{}\n]\n", days.join(",\n"))
+            }
+        }
+    }
+
+    pub fn growth(&self) -> Growth {
+        let (first, last) = (
+            self.populations().next().unwrap(),
+            self.populations().last().unwrap(),
+        );
+        let days = self.histograms.len() - 1;
+
+        Growth {
+            days,
+            observed: (first.as_f64() > 0.0 && days > 0)
+                .then(|| (last.as_f64() / first.as_f64()).powf(1.0 / days as f64) - 1.0),
+            ratio: self.species.growth_ratio(),
+        }
+    }
+}
+
+pub struct Growth {
+    days: usize,
+    // compound daily growth rate over the series
+    observed: Option<f64>,
+    // the long run daily growth factor of the model
+    ratio: f64,
+}
 
-        Ok(School(fish_by_age))
+impl Growth {
+    fn doubling_time(&self) -> Option<f64> {
+        (self.ratio > 1.0).then(|| 2f64.ln() / self.ratio.ln())
+    }
+}
+
+impl Display for Growth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.observed {
+            Some(rate) => writeln!(
+                f,
+                "daily growth {:.4}% over {} days",
+                rate * 100.0,
+                self.days
+            )?,
+            None => writeln!(f, "no growth to observe over {} days", self.days)?,
+        }
+
+        writeln!(f, "asymptotic ratio {:.6}", self.ratio)?;
+
+        match self.doubling_time() {
+            Some(days) => writeln!(f, "doubling time {:.2} days", days),
+            None => writeln!(f, "the school never doubles"),
+        }
+    }
+}
+
+impl<C: Count> FromStr for School<C> {
+    type Err = ParseSchoolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        School::parse(s, &Species::default())
     }
 }
 
 pub fn part1(input: &str) -> u64 {
-    let mut school = School::from_str(input).unwrap();
-    school.n_steps(80);
-    school.population()
+    population(input, &Species::default(), 80).unwrap()
 }
 
 pub fn part2(input: &str) -> u64 {
-    let mut school = School::from_str(input).unwrap();
-    school.n_steps(256);
-    school.population()
+    population(input, &Species::default(), 256).unwrap()
+}
+
+pub fn population<C: Count>(input: &str, species: &Species, days: u32) -> Result<C, SchoolError> {
+    let mut school = School::parse(input, species)?;
+    school.n_steps(days)?;
+    Ok(school.population().unwrap())
+}
+
+// fish per timer value after `days`
+pub fn histogram<C: Count>(
+    input: &str,
+    species: &Species,
+    days: u32,
+) -> Result<Vec<C>, SchoolError> {
+    let mut school = School::parse(input, species)?;
+    school.n_steps(days)?;
+    Ok(school.histogram().to_vec())
+}
+
+pub fn series<C: Count>(
+    input: &str,
+    species: &Species,
+    days: u32,
+) -> Result<Series<C>, SchoolError> {
+    Ok(School::parse(input, species)?.record(days)?)
+}
+
+// None if the population never gets there
+pub fn first_day_reaching(
+    input: &str,
+    species: &Species,
+    target: u128,
+) -> Result<Option<u64>, SchoolError> {
+    Ok(School::parse(input, species)?.first_day_reaching(target))
+}
+
+pub fn projection(
+    input: &str,
+    species: &Species,
+    days: u64,
+    modulus: u64,
+) -> Result<u64, SchoolError> {
+    if modulus == 0 {
+        return Err(SchoolError::ZeroModulus);
+    }
+
+    School::parse(input, species)?
+        .population_after(days, modulus)
+        .ok_or(SchoolError::Mortality)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn school(fish: &str) -> School {
+        School::from_str(fish).unwrap()
+    }
+
     #[test]
     fn example() {
-        let mut school80 = School::from_str("3,4,3,1,2").unwrap();
-        let mut school256 = School::from_str("3,4,3,1,2").unwrap();
+        let mut school80 = school("3,4,3,1,2");
+        let mut school256 = school("3,4,3,1,2");
+
+        school80.n_steps(80).unwrap();
+        school256.n_steps(256).unwrap();
+
+        assert_eq!(school80.population(), Some(5934));
+        assert_eq!(school256.population(), Some(26984457539));
+    }
+
+    // the example's fish, listed after each day
+    const DAYS: [&str; 19] = [
+        "3,4,3,1,2",
+        "2,3,2,0,1",
+        "1,2,1,6,0,8",
+        "0,1,0,5,6,7,8",
+        "6,0,6,4,5,6,7,8,8",
+        "5,6,5,3,4,5,6,7,7,8",
+        "4,5,4,2,3,4,5,6,6,7",
+        "3,4,3,1,2,3,4,5,5,6",
+        "2,3,2,0,1,2,3,4,4,5",
+        "1,2,1,6,0,1,2,3,3,4,8",
+        "0,1,0,5,6,0,1,2,2,3,7,8",
+        "6,0,6,4,5,6,0,1,1,2,6,7,8,8,8",
+        "5,6,5,3,4,5,6,0,0,1,5,6,7,7,7,8,8",
+        "4,5,4,2,3,4,5,6,6,0,4,5,6,6,6,7,7,8,8",
+        "3,4,3,1,2,3,4,5,5,6,3,4,5,5,5,6,6,7,7,8",
+        "2,3,2,0,1,2,3,4,4,5,2,3,4,4,4,5,5,6,6,7",
+        "1,2,1,6,0,1,2,3,3,4,1,2,3,3,3,4,4,5,5,6,8",
+        "0,1,0,5,6,0,1,2,2,3,0,1,2,2,2,3,3,4,4,5,7,8",
+        "6,0,6,4,5,6,0,1,1,2,6,0,1,1,1,2,2,3,3,4,6,7,8,8,8,8",
+    ];
+
+    #[test]
+    fn each_day() {
+        let mut school = school(DAYS[0]);
+
+        for (day, fish) in DAYS.iter().enumerate() {
+            let expected = self::school(fish);
+
+            assert_eq!(school.histogram(), expected.histogram(), "day {}", day);
+            assert_eq!(school.population(), Some(fish.split(",").count() as u64));
+            school.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn histogram() {
+        assert_eq!(
+            school("3,4,3,1,2\n").histogram(),
+            [0, 1, 1, 2, 1, 0, 0, 0, 0]
+        );
+        assert!(School::<u64>::from_str("3,x").is_err());
+        assert!(School::<u64>::from_str("9").is_err());
+    }
+
+    #[test]
+    fn population_after() {
+        let school = school("3,4,3,1,2");
+
+        assert_eq!(school.population_after(80, u64::MAX), Some(5934));
+        assert_eq!(school.population_after(256, u64::MAX), Some(26984457539));
+
+        for days in 0..300 {
+            let mut stepwise = self::school("3,4,3,1,2");
+            stepwise.n_steps(days).unwrap();
+
+            assert_eq!(
+                school.population_after(days as u64, u64::MAX),
+                stepwise.population()
+            );
+        }
+    }
+
+    #[test]
+    fn population_after_modulo() {
+        let modulus = 1_000_000_007;
+        let school = school("3,4,3,1,2");
+        let mut stepwise = self::school("3,4,3,1,2");
+
+        for days in 0..2000 {
+            assert_eq!(
+                school.population_after(days, modulus),
+                stepwise.population().map(|p| p % modulus)
+            );
+
+            stepwise.step().unwrap();
+            stepwise.fish.iter_mut().for_each(|fish| *fish %= modulus);
+        }
+
+        assert!(school.population_after(1_000_000_000_000, modulus).unwrap() < modulus);
+        assert_eq!(school.population_after(10, 0), None);
+    }
+
+    fn species(args: &str) -> Species {
+        Species::from_args(args.split_whitespace().map(String::from)).unwrap()
+    }
+
+    fn parse(fish: &str, species: &Species) -> School {
+        School::parse(fish, species).unwrap()
+    }
+
+    #[test]
+    fn species_args() {
+        assert_eq!(species(""), Species::default());
+        assert_eq!(
+            species("--cycle 5 --maturity 0 --mortality 1/10"),
+            Species {
+                cycle: 5,
+                maturity: 0,
+                mortality: (1, 10)
+            }
+        );
+
+        for args in [
+            "--cycle 0",
+            "--mortality 2/1",
+            "--mortality 1/0",
+            "--cycle",
+            "--age 3",
+        ] {
+            assert!(Species::from_args(args.split_whitespace().map(String::from)).is_err());
+        }
+    }
+
+    #[test]
+    fn other_species() {
+        // without a maturity delay a fish's offspring keep its timer, so the
+        // population doubles every cycle
+        let doubling = species("--cycle 3 --maturity 0");
+        let mut school = parse("0,1,2", &doubling);
+        school.n_steps(9).unwrap();
+        assert_eq!(school.histogram(), [8, 8, 8]);
+
+        let slow = species("--cycle 2 --maturity 3");
+        let mut school = parse("0", &slow);
+        let populations = (0..8)
+            .map(|_| {
+                school.step().unwrap();
+                school.population().unwrap()
+            })
+            .collect::<Vec<u64>>();
+        assert_eq!(populations, [2, 2, 3, 3, 4, 5, 6, 8]);
+        assert!(School::<u64>::parse("5", &slow).is_err());
+
+        for species in [doubling, slow] {
+            assert_eq!(
+                parse("0,1", &species).population_after(20, u64::MAX),
+                population("0,1", &species, 20).ok()
+            );
+        }
+    }
+
+    #[test]
+    fn mortality() {
+        let dying = species("--mortality 1/2");
+        let mut school = parse("0,0,0,0,3,3,3", &dying);
+
+        school.step().unwrap();
+        assert_eq!(school.histogram(), [0, 0, 2, 0, 0, 0, 2, 0, 2]);
+        assert_eq!(school.population_after(1, u64::MAX), None);
+
+        // rounding down spares the last fish of every group
+        school.n_steps(100).unwrap();
+        assert!(school.population().unwrap() < 20);
+
+        let mut school = parse("0,0,0,0,3,3,3", &species("--mortality 1/1"));
+        school.step().unwrap();
+        assert_eq!(school.population(), Some(0));
+    }
+
+    #[test]
+    fn export_series() {
+        let series = school("3,4,3,1,2").record(2).unwrap();
+
+        assert_eq!(
+            series.export(Format::Csv),
+            concat!(
+                "day,population,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8\n",
+                "0,5,0,1,1,2,1,0,0,0,0\n",
+                "1,5,1,1,2,1,0,0,0,0,0\n",
+                "2,6,1,2,1,0,0,0,1,0,1\n",
+            )
+        );
+        assert_eq!(
+            series.export(Format::Json),
+            concat!(
+                "[\n",
+                "  {\"day\": 0, \"population\": 5, \"timers\": [0,1,1,2,1,0,0,0,0]},\n",
+                "  {\"day\": 1, \"population\": 5, \"timers\": [1,1,2,1,0,0,0,0,0]},\n",
+                "  {\"day\": 2, \"population\": 6, \"timers\": [1,2,1,0,0,0,1,0,1]}\n",
+                "]\n",
+            )
+        );
+        assert!(Format::from_str("xml").is_err());
+    }
+
+    #[test]
+    fn growth() {
+        let growth = school("3,4,3,1,2").record(256).unwrap().growth();
+        let ratio = growth.ratio;
+
+        assert!((ratio.powi(9) - ratio.powi(2) - 1.0).abs() < 1e-9);
+        assert!((growth.observed.unwrap() + 1.0 - ratio).abs() < 1e-3);
+        assert!((growth.doubling_time().unwrap() - 7.9566).abs() < 1e-3);
+
+        let doubling = species("--cycle 3 --maturity 0");
+        assert!((doubling.growth_ratio() - 2f64.powf(1.0 / 3.0)).abs() < 1e-12);
+        assert!((species("--cycle 1 --maturity 0").growth_ratio() - 2.0).abs() < 1e-12);
+
+        let dying = species("--mortality 1/2");
+        let growth = |days| parse("1", &dying).record(days).unwrap().growth();
+        assert!(growth(10).doubling_time().is_none());
+        assert!(growth(0).observed.is_none());
+    }
+
+    #[test]
+    fn overflow() {
+        let mut school = school("3,4,3,1,2");
+        let day = match school.n_steps(1000) {
+            Err(OverflowError { day }) => day,
+            Ok(()) => panic!("no overflow"),
+        };
+
+        // the last day that fits, exactly
+        let mut exact = School::<u128>::from_str("3,4,3,1,2").unwrap();
+        exact.n_steps(day - 1).unwrap();
+        assert!(exact.population().unwrap() <= u64::MAX as u128);
+
+        exact.step().unwrap();
+        assert!(exact.population().unwrap() > u64::MAX as u128);
+        assert!(population::<u64>("3,4,3,1,2", &Species::default(), day - 1).is_ok());
+        assert!(series::<u64>("3,4,3,1,2", &Species::default(), day).is_err());
+
+        // u128 agrees with the projection and overflows too, much later
+        let modulus = 1_000_000_007;
+        let projected = self::school("3,4,3,1,2").population_after(800, modulus);
+        let exact = population::<u128>("3,4,3,1,2", &Species::default(), 800).unwrap();
+        assert_eq!(projected, Some((exact % modulus as u128) as u64));
+
+        match population::<u128>("3,4,3,1,2", &Species::default(), 2000) {
+            Err(SchoolError::Overflow(late)) => {
+                assert!(late.day > 2 * day - 100 && late.day < 2000)
+            }
+            _ => panic!("no overflow"),
+        }
+    }
+
+    #[test]
+    fn school_errors() {
+        let short = species("--cycle 3 --maturity 0");
+
+        assert_eq!(
+            population::<u64>("3,4,3,1,2", &short, 80)
+                .unwrap_err()
+                .to_string(),
+            "invalid timer: 3"
+        );
+        assert!(matches!(
+            projection("3,4,3,1,2", &Species::default(), 10, 0),
+            Err(SchoolError::ZeroModulus)
+        ));
+        assert!(matches!(
+            projection("3,4,3,1,2", &species("--mortality 1/2"), 10, 7),
+            Err(SchoolError::Mortality)
+        ));
+        assert!(super::first_day_reaching("9", &Species::default(), 10).is_err());
+    }
+
+    #[test]
+    fn u128_deaths() {
+        assert_eq!(u128::MAX.deaths((1, 1)), u128::MAX);
+        assert_eq!(u128::MAX.deaths((1, 2)), u128::MAX / 2);
+        assert_eq!(7u128.deaths((2, 3)), 4);
+        assert_eq!(7u64.deaths((2, 3)), 4);
+    }
+
+    #[test]
+    fn first_day_reaching() {
+        let school = school("3,4,3,1,2");
+        let mut stepwise = self::school("3,4,3,1,2");
+        let mut previous = 0;
+
+        // populations above the day before's are first reached that day
+        for day in 0..100 {
+            let population = stepwise.population().unwrap() as u128;
+
+            if population > previous {
+                for target in [previous + 1, population] {
+                    assert_eq!(school.first_day_reaching(target), Some(day));
+                }
+            }
+
+            previous = population;
+            stepwise.step().unwrap();
+        }
+
+        assert_eq!(school.first_day_reaching(0), Some(0));
+        assert_eq!(school.first_day_reaching(5934), Some(80));
+        assert_eq!(school.first_day_reaching(5935), Some(81));
+        assert_eq!(school.first_day_reaching(26984457539), Some(256));
+        assert!(school.first_day_reaching(u128::MAX).unwrap() > 900);
+
+        let empty = School {
+            species: Species::default(),
+            fish: vec![0; 9],
+        };
+        assert_eq!(empty.first_day_reaching(1), None);
+    }
+
+    #[test]
+    fn first_day_reaching_with_deaths() {
+        let dying = species("--mortality 1/2");
+        assert_eq!(parse("1", &dying).first_day_reaching(1), Some(0));
+        assert_eq!(
+            parse("0,0,0,0,3,3,3", &dying).first_day_reaching(8),
+            Some(19)
+        );
+        assert_eq!(
+            parse("0,3", &species("--mortality 1/1")).first_day_reaching(3),
+            None
+        );
 
-        school80.n_steps(80);
-        school256.n_steps(256);
+        let thinning = species("--mortality 1/100");
+        let mut stepwise = School::<u128>::parse("3,4,3,1,2", &thinning).unwrap();
+        stepwise.n_steps(200).unwrap();
+        let target = stepwise.population().unwrap();
 
-        assert_eq!(school80.population(), 5934);
-        assert_eq!(school256.population(), 26984457539);
+        let day = parse("3,4,3,1,2", &thinning)
+            .first_day_reaching(target)
+            .unwrap();
+        assert!(day <= 200);
+        assert!(parse("3,4,3,1,2", &thinning)
+            .first_day_reaching(u128::MAX)
+            .is_some());
     }
 }
@@ -1,46 +1,210 @@
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-fn median(nums: &mut [u64]) -> u64 {
-    nums.sort();
+// where the crabs line up and what it costs them
+#[derive(Debug, PartialEq)]
+pub struct Alignment {
+    position: u64,
+    fuel: u64,
+}
 
-    if nums.len() % 2 == 0 {
-        nums[nums.len() / 2]
-    } else {
-        (nums[(nums.len() / 2)] + nums[(nums.len() / 2) + 1]) / 2
+impl Display for Alignment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} fuel to align at {}", self.fuel, self.position)
     }
 }
 
-pub fn part1(input: &str) -> u64 {
-    let mut nums = input
+fn parse(input: &str) -> Vec<u64> {
+    input
         .split(",")
-        .map(|n| u64::from_str(n).unwrap())
-        .collect::<Vec<u64>>();
+        .map(|n| u64::from_str(n.trim()).unwrap())
+        .collect()
+}
 
-    let med = median(nums.as_mut());
+fn linear_fuel(crabs: &[u64], position: u64) -> u64 {
+    crabs.iter().map(|&crab| crab.abs_diff(position)).sum()
+}
 
-    nums.into_iter().fold(0, |fuel, position| {
-        fuel + i64::abs(position as i64 - med as i64) as u64
+// moving one step costs one fuel, so any position between the two middle
+// crabs is optimal. this takes the lower one. None without crabs
+fn align_linear(crabs: &mut [u64]) -> Option<Alignment> {
+    crabs.sort_unstable();
+
+    let position = *crabs.get(crabs.len().checked_sub(1)? / 2)?;
+
+    Some(Alignment {
+        position,
+        fuel: linear_fuel(crabs, position),
     })
 }
 
+fn triangular_fuel(crabs: &[u64], position: u64) -> u64 {
+    crabs
+        .iter()
+        .map(|&crab| crab.abs_diff(position))
+        .map(|d| d * (d + 1) / 2)
+        .sum()
+}
+
+// with each step costing one more than the last, the fuel is convex and its
+// real minimum lies within half a step of the mean, so the best position is
+// one of the few integers from mean - 1/2 to mean + 1/2 rounded outwards
+fn align_triangular(crabs: &[u64]) -> Option<Alignment> {
+    let (sum, n) = (crabs.iter().sum::<u64>(), crabs.len() as u64);
+    let lo = (2 * sum).saturating_sub(n) / (2 * n.max(1));
+    let hi = (2 * sum + n).div_ceil(2 * n.max(1));
+
+    (lo..=hi)
+        .filter(|_| n > 0)
+        .map(|position| Alignment {
+            position,
+            fuel: triangular_fuel(crabs, position),
+        })
+        .min_by_key(|a| a.fuel)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Cost {
+    // one fuel per step
+    Linear,
+    // one more fuel for each step than the step before
+    Triangular,
+}
+
+pub fn alignment(input: &str, cost: Cost) -> Option<Alignment> {
+    let mut crabs = parse(input);
+
+    match cost {
+        Cost::Linear => align_linear(&mut crabs),
+        Cost::Triangular => align_triangular(&crabs),
+    }
+}
+
+pub fn part1(input: &str) -> u64 {
+    alignment(input, Cost::Linear).map_or(0, |a| a.fuel)
+}
+
 pub fn part2(input: &str) -> u64 {
-    let mut nums = input
-        .split(",")
-        .map(|n| u64::from_str(n).unwrap())
-        .collect::<Vec<u64>>();
+    alignment(input, Cost::Triangular).map_or(0, |a| a.fuel)
+}
 
-    let usage = |d| (d * (d + 1)) / 2;
-    let mut min_usage = f64::INFINITY;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    const EXAMPLE: &str = "16,1,2,0,4,2,7,1,2,14";
+
+    fn align(crabs: &[u64]) -> Option<Alignment> {
+        align_linear(&mut crabs.to_vec())
+    }
 
-    nums.sort();
+    // the cheapest fuel over every position the crabs span
+    fn brute_force(crabs: &[u64], fuel: fn(&[u64], u64) -> u64) -> u64 {
+        let (lo, hi) = (crabs.iter().min().unwrap(), crabs.iter().max().unwrap());
 
-    for &i in &nums {
-        let mut total_usage: u64 = 0;
-        for &j in &nums {
-            total_usage += usage(i64::abs(j as i64 - i as i64) as u64);
+        (*lo..=*hi).map(|p| fuel(crabs, p)).min().unwrap()
+    }
+
+    // the original part 2, which only tries positions a crab already holds
+    fn occupied_positions(input: &str) -> u64 {
+        let mut nums = input
+            .split(",")
+            .map(|n| u64::from_str(n).unwrap())
+            .collect::<Vec<u64>>();
+
+        let usage = |d| (d * (d + 1)) / 2;
+        let mut min_usage = f64::INFINITY;
+
+        nums.sort();
+
+        for &i in &nums {
+            let mut total_usage: u64 = 0;
+            for &j in &nums {
+                total_usage += usage(i64::abs(j as i64 - i as i64) as u64);
+            }
+            min_usage = f64::min(min_usage, total_usage as f64)
+        }
+
+        min_usage as u64
+    }
+
+    fn crabs(seed: u64, len: usize, range: u64) -> Vec<u64> {
+        let mut rng = Rng(seed);
+        (0..len).map(|_| rng.below(range)).collect()
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE), 37);
+        assert_eq!(
+            alignment(EXAMPLE, Cost::Linear),
+            Some(Alignment {
+                position: 2,
+                fuel: 37
+            })
+        );
+
+        assert_eq!(part2(EXAMPLE), 168);
+        assert_eq!(
+            alignment(EXAMPLE, Cost::Triangular),
+            Some(Alignment {
+                position: 5,
+                fuel: 168
+            })
+        );
+        // no crab starts at 5
+        assert_eq!(occupied_positions(EXAMPLE), 170);
+    }
+
+    #[test]
+    fn linear_alignment() {
+        let alignment = |crabs: &[u64]| align(crabs).map(|a| (a.position, a.fuel));
+
+        assert_eq!(alignment(&[9, 1, 2]), Some((2, 8)));
+        assert_eq!(alignment(&[10, 3, 1, 2]), Some((2, 10)));
+        assert_eq!(alignment(&[5]), Some((5, 0)));
+        assert_eq!(alignment(&[3, 100, 3, 3, 3]), Some((3, 97)));
+        assert_eq!(alignment(&[7, 7, 0, 0]), Some((0, 14)));
+        assert_eq!(alignment(&[]), None);
+    }
+
+    #[test]
+    fn linear_matches_brute_force() {
+        for len in 1..50 {
+            let crabs = crabs(len as u64 * 0x9e37_79b9, len, 100);
+
+            assert_eq!(
+                align(&crabs).unwrap().fuel,
+                brute_force(&crabs, linear_fuel)
+            );
         }
-        min_usage = f64::min(min_usage, total_usage as f64)
     }
 
-    min_usage as u64
+    #[test]
+    fn triangular_alignment() {
+        let alignment = |crabs: &[u64]| align_triangular(crabs).map(|a| (a.position, a.fuel));
+
+        assert_eq!(alignment(&[5]), Some((5, 0)));
+        assert_eq!(alignment(&[0, 1]), Some((0, 1)));
+        assert_eq!(alignment(&[0, 3]), Some((1, 4)));
+        assert_eq!(alignment(&[0, 0, 0, 10]), Some((2, 45)));
+        assert_eq!(alignment(&[]), None);
+    }
+
+    #[test]
+    fn triangular_matches_brute_force() {
+        for len in 1..50 {
+            let crabs = crabs(len as u64 * 0x2545_f491, len, 100);
+            let input = crabs
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+            let fuel = part2(&input);
+
+            assert_eq!(fuel, brute_force(&crabs, triangular_fuel));
+            assert!(fuel <= occupied_positions(&input));
+        }
+    }
 }
@@ -20,7 +20,7 @@ fn numbers_with_segment_count(segment_count: u8) -> Vec<u8> {
 
 pub fn part1(input: &[String]) -> u16 {
     let output_values = input
-        .into_iter()
+        .iter()
         .map(|reading| reading.split(" | ").collect::<Vec<&str>>()[1])
         .flat_map(|digits| digits.split_whitespace());
 
@@ -82,11 +82,11 @@ impl Signal {
     }
 }
 
-fn digits_by_signal(digits: &Vec<Signal>) -> HashMap<Signal, u8> {
+fn digits_by_signal(digits: &[Signal]) -> HashMap<Signal, u8> {
     let mut candidate_signals_by_number: HashMap<u8, HashSet<Signal>> = HashMap::new();
     let mut known_signals_by_number: HashMap<u8, Signal> = HashMap::new();
 
-    digits.into_iter().clone().for_each(|signal| {
+    digits.iter().for_each(|signal| {
         let possible_numbers = numbers_with_segment_count(signal.len() as u8);
 
         if possible_numbers.len() == 1 {
@@ -98,7 +98,7 @@ fn digits_by_signal(digits: &Vec<Signal>) -> HashMap<Signal, u8> {
                 .for_each(|&possible_number| {
                     candidate_signals_by_number
                         .entry(possible_number)
-                        .or_insert(HashSet::new())
+                        .or_default()
                         .insert(signal.clone());
                 })
         }
@@ -159,9 +159,8 @@ fn digits_by_signal(digits: &Vec<Signal>) -> HashMap<Signal, u8> {
     let (nine, zero_and_six): (Vec<Signal>, Vec<Signal>) = six_character_signals.partition(|str| {
         chars_4
             .difference(&HashSet::from_iter(str.chars.chars()))
-            .collect::<Vec<&char>>()
-            .len()
-            == 0
+            .next()
+            .is_none()
     });
 
     println!("{:?}, {:?}", zero_and_six, nine);
@@ -235,6 +234,6 @@ mod tests {
 
     #[test]
     fn inspect() {
-        drop(part2(&[String::from(EXAMPLE)]))
+        part2(&[String::from(EXAMPLE)]);
     }
 }
@@ -1,9 +1,11 @@
 use std::env;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
+use std::process;
 use std::str::FromStr;
 
+mod args;
 mod day01;
 mod day02;
 mod day03;
@@ -12,6 +14,7 @@ mod day05;
 mod day06;
 mod day07;
 mod day08;
+mod rng;
 
 fn main() {
     match env::args().nth(1).unwrap().as_str() {
@@ -20,13 +23,72 @@ fn main() {
         "2" => println!("{}", day02::solve(&read_lines_stdin())),
         "3a" => println!("{}", day03::part1(&read_lines_stdin())),
         "3b" => println!("{}", day03::part2(&read_lines_stdin())),
-        "4a" => println!("{}", day04::part1(&read_lines_stdin())),
-        "4b" => println!("{}", day04::part2(&read_lines_stdin())),
-        "5a" => println!("{}", day05::part1(&read_lines_stdin())),
-        "5b" => println!("{}", day05::part2(&read_lines_stdin())),
+        "4a" => println!(
+            "{}",
+            or_exit(day04::part1(&read_lines_stdin(), &day04_rules(2)))
+        ),
+        "4b" => println!(
+            "{}",
+            or_exit(day04::part2(&read_lines_stdin(), &day04_rules(2)))
+        ),
+        "4n" => println!(
+            "{}",
+            or_exit(day04::nth(
+                &read_lines_stdin(),
+                &day04_rules(3),
+                env::args().nth(2).unwrap().parse().unwrap()
+            ))
+        ),
+        "4s" => print!(
+            "{}",
+            or_exit(day04::analyse(&read_lines_stdin(), &day04_rules(2)))
+        ),
+        "4g" => print!(
+            "{}",
+            or_exit(day04::Generator::from_args(env::args().skip(2))).generate()
+        ),
+        "4t" if has_flag("--watch") => or_exit(day04::watch(&read_lines_stdin(), &day04_rules(2))),
+        "4t" => print!(
+            "{}",
+            or_exit(day04::Game::new(&read_lines_stdin(), &day04_rules(2)))
+        ),
+        "5a" => println!("{}", day05::part1(&read_lines_stdin(), day05_engine())),
+        "5b" => println!("{}", day05::part2(&read_lines_stdin(), day05_engine())),
+        "5c" => println!("{}", day05::part3(&read_lines_stdin(), day05_engine())),
+        "5e" => {
+            let format = or_exit(env::args().nth(2).unwrap().parse::<day05::ImageFormat>());
+            let lines = read_lines_stdin();
+            let image = or_exit(day05::export(&lines, format, has_flag("--overlay")));
+            io::stdout().write_all(&image).unwrap()
+        }
+        "5q" => day05_query(&read_lines_stdin()),
+        "5p" => day05_route(&read_lines_stdin()),
         "6a" => println!("{}", day06::part1(&read_lines_stdin::<String>().concat())),
         "6b" => println!("{}", day06::part2(&read_lines_stdin::<String>().concat())),
+        "6n" | "6h" | "6t" | "6g" if has_flag("--u128") => day06_simulate::<u128>(),
+        "6n" | "6h" | "6t" | "6g" => day06_simulate::<u64>(),
+        "6r" => match or_exit(day06::first_day_reaching(
+            &read_lines_stdin::<String>().concat(),
+            &day06_species(3),
+            env::args().nth(2).unwrap().parse().unwrap(),
+        )) {
+            Some(day) => println!("{}", day),
+            None => println!("never"),
+        },
+        "6p" => println!(
+            "{}",
+            or_exit(day06::projection(
+                &read_lines_stdin::<String>().concat(),
+                &day06_species(4),
+                env::args().nth(2).unwrap().parse().unwrap(),
+                env::args().nth(3).unwrap().parse().unwrap()
+            ))
+        ),
         "7a" => println!("{}", day07::part1(&read_lines_stdin::<String>().concat())),
+        "7m" => println!(
+            "{}",
+            day07::alignment(&read_lines_stdin::<String>().concat(), day07_cost()).unwrap()
+        ),
         "7b" => println!("{}", day07::part2(&read_lines_stdin::<String>().concat())),
         "8a" => println!("{}", day08::part1(&read_lines_stdin())),
         "8b" => println!("{}", day08::part2(&read_lines_stdin())),
@@ -34,6 +96,138 @@ fn main() {
     }
 }
 
+// day 4 options follow the command and any positional arguments
+fn day04_rules(skip: usize) -> day04::Rules {
+    or_exit(day04::Rules::from_args(
+        env::args().skip(skip).filter(|arg| arg != "--watch"),
+    ))
+}
+
+// day 6 species options follow the command and its positional arguments
+fn day06_species(skip: usize) -> day06::Species {
+    or_exit(day06::Species::from_args(
+        env::args().skip(skip).filter(|arg| arg != "--u128"),
+    ))
+}
+
+// `6n DAYS`, `6h DAYS`, `6t DAYS FORMAT` or `6g DAYS`, counting fish with C
+fn day06_simulate<C: day06::Count>() {
+    let input = read_lines_stdin::<String>().concat();
+    let arg = |n| env::args().nth(n).unwrap();
+    let days = arg(2).parse().unwrap();
+
+    match arg(1).as_str() {
+        "6n" => println!(
+            "{}",
+            or_exit(day06::population::<C>(&input, &day06_species(3), days))
+        ),
+        "6h" => or_exit(day06::histogram::<C>(&input, &day06_species(3), days))
+            .iter()
+            .enumerate()
+            .for_each(|(timer, fish)| println!("{} {}", timer, fish)),
+        "6t" => print!(
+            "{}",
+            or_exit(day06::series::<C>(&input, &day06_species(4), days))
+                .export(arg(3).parse().unwrap())
+        ),
+        "6g" => print!(
+            "{}",
+            or_exit(day06::series::<C>(&input, &day06_species(3), days)).growth()
+        ),
+        _ => (),
+    }
+}
+
+fn day05_engine() -> day05::Engine {
+    if has_flag("--sweep") {
+        day05::Engine::Sweep
+    } else {
+        day05::Engine::Raster
+    }
+}
+
+// `5q hot K`, `5q at X,Y`, `5q sum X,Y X,Y` or `5q clusters MIN [--eight]`
+fn day05_query(lines: &[day05::Line]) {
+    let map = or_exit(day05::VentMap::new(lines));
+    let arg = |n| env::args().nth(n).unwrap();
+
+    match arg(2).as_str() {
+        "hot" => map
+            .hotspots(arg(3).parse().unwrap())
+            .iter()
+            .for_each(|(point, count)| println!("{} {}", point, count)),
+        "at" => {
+            let point = arg(3).parse().unwrap();
+            println!("{}", map.get(point));
+            map.covering(point).iter().for_each(|l| println!("{}", l));
+        }
+        "sum" => println!(
+            "{}",
+            map.region_sum(arg(3).parse().unwrap(), arg(4).parse().unwrap())
+        ),
+        "clusters" => {
+            for cluster in map.clusters(arg(3).parse().unwrap(), day05_connectivity()) {
+                println!(
+                    "{}",
+                    cluster
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                );
+            }
+        }
+        _ => (),
+    }
+}
+
+// `5p X,Y X,Y THRESHOLD [--eight]`
+fn day05_route(lines: &[day05::Line]) {
+    let map = or_exit(day05::VentMap::new(lines));
+    let arg = |n| env::args().nth(n).unwrap();
+
+    match map.route(
+        arg(2).parse().unwrap(),
+        arg(3).parse().unwrap(),
+        arg(4).parse().unwrap(),
+        day05_connectivity(),
+    ) {
+        Some(route) => {
+            println!("{} steps, cost {}", route.steps(), route.cost());
+            println!("{}", map.render(&route));
+        }
+        None => println!("no safe route"),
+    }
+}
+
+fn day05_connectivity() -> day05::Connectivity {
+    if has_flag("--eight") {
+        day05::Connectivity::Eight
+    } else {
+        day05::Connectivity::Four
+    }
+}
+
+fn day07_cost() -> day07::Cost {
+    if has_flag("--triangular") {
+        day07::Cost::Triangular
+    } else {
+        day07::Cost::Linear
+    }
+}
+
+// prints the error instead of panicking, for bad input rather than bugs
+fn or_exit<T, E: Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1)
+    })
+}
+
+fn has_flag(flag: &str) -> bool {
+    env::args().any(|arg| arg == flag)
+}
+
 fn read_lines_stdin<T: FromStr>() -> Vec<T>
 where
     <T as FromStr>::Err: Debug,
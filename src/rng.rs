@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+// splitmix64, so generated input and test fixtures are reproducible without a
+// rand dependency
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in 0..n
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next() as u128 * n as u128) >> 64) as u64
+    }
+
+    // `count` distinct numbers from min..=max in random order. this is a
+    // partial Fisher-Yates shuffle that only stores the swapped slots, so
+    // huge ranges are fine
+    pub fn sample(&mut self, min: u32, max: u32, count: usize) -> Vec<u32> {
+        let len = (max - min) as u64 + 1;
+        let mut swapped: HashMap<u64, u64> = HashMap::new();
+
+        (0..count as u64)
+            .map(|i| {
+                let j = i + self.below(len - i);
+                let picked = *swapped.get(&j).unwrap_or(&j);
+                swapped.insert(j, *swapped.get(&i).unwrap_or(&i));
+                min + picked as u32
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn below() {
+        let mut rng = Rng(1);
+
+        assert!((0..1000).all(|_| rng.below(7) < 7));
+        assert_eq!(rng.below(1), 0);
+    }
+
+    #[test]
+    fn sample() {
+        let mut sample = Rng(1).sample(10, 19, 10);
+
+        assert_eq!(sample, Rng(1).sample(10, 19, 10));
+        sample.sort();
+        assert_eq!(sample, (10..20).collect::<Vec<u32>>());
+        assert_eq!(Rng(1).sample(0, u32::MAX, 3).len(), 3);
+    }
+}